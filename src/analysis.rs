@@ -0,0 +1,241 @@
+use std::time::Duration;
+
+use image::{imageops, RgbImage};
+
+use crate::{FrameView, PixelFormat, Result, VidSeqError, VideoSequence};
+
+/// Frames that differ from the searched hash by more bits than this are not considered a match.
+const MAX_HASH_DISTANCE: u32 = 10;
+
+/// Frames with an average luma at or below this are black to `VideoSequence::trim_black_bounds`,
+/// which leaves some room for noise above studio-range black (16).
+const BLACK_THRESHOLD: u8 = 24;
+
+/// Irregularities in the frame timestamps of a video, see `VideoSequence::analyze_continuity`.
+#[derive(Debug, Clone, Default)]
+pub struct ContinuityReport {
+    /// Frames that carry the same timestamp as the frame before them.
+    pub duplicates: Vec<u64>,
+    /// Pairs of frames with more than one frame interval between them, as `(before, after)`.
+    pub gaps: Vec<(u64, u64)>,
+}
+
+/// Computes the 64-bit average hash of an image.
+///
+/// The image is shrunk to 8x8 grayscale pixels, and every bit tells whether that pixel is brighter than the average;
+/// similar-looking images end up with hashes that differ in only a few bits.
+pub fn average_hash(img: &RgbImage) -> u64 {
+    let small = imageops::resize(
+        &imageops::grayscale(img),
+        8,
+        8,
+        imageops::FilterType::Triangle,
+    );
+
+    let mean = small.pixels().map(|p| p.0[0] as u32).sum::<u32>() / 64;
+
+    small
+        .pixels()
+        .enumerate()
+        .filter(|(_, p)| p.0[0] as u32 > mean)
+        .fold(0, |hash, (i, _)| hash | 1 << i)
+}
+
+/// Counts how many pixels of `frame` fall into each of the 256 luma levels, using the BT.601 weights for color frames.
+fn luma_histogram(frame: &FrameView) -> Result<[u32; 256]> {
+    let mut histogram = [0u32; 256];
+
+    for y in 0..frame.height() {
+        let row = frame.row(y).ok_or_else(|| {
+            VidSeqError::InvalidFrame("could not read full image buffer".to_owned())
+        })?;
+
+        if frame.format() == PixelFormat::Gray8 {
+            for &luma in row {
+                histogram[luma as usize] += 1;
+            }
+
+            continue;
+        }
+
+        for pixel in row.chunks_exact(frame.format().channels()) {
+            let luma = (77 * pixel[0] as u32 + 150 * pixel[1] as u32 + 29 * pixel[2] as u32) >> 8;
+
+            histogram[luma as usize] += 1;
+        }
+    }
+
+    Ok(histogram)
+}
+
+impl VideoSequence {
+    /// Looks for the frame that is most similar to `target_hash`, see `average_hash`.
+    ///
+    /// Only every `step`-th frame is checked, the best match is returned if it is close enough to the target.
+    pub fn find_frame_by_hash(
+        &mut self,
+        target_hash: u64,
+        step: u64,
+    ) -> Result<Option<(u64, RgbImage)>> {
+        if step == 0 {
+            return Err(VidSeqError::InvalidArgument(
+                "step cannot be zero".to_owned(),
+            ));
+        }
+
+        let mut best: Option<(u32, u64, RgbImage)> = None;
+
+        for index in (0..self.len()).step_by(step as usize) {
+            let frame = match self.get_frame(index)? {
+                Some(frame) => frame,
+                None => break,
+            };
+
+            let distance = (average_hash(&frame) ^ target_hash).count_ones();
+
+            if best.as_ref().is_none_or(|(d, _, _)| distance < *d) {
+                best = Some((distance, index, frame));
+            }
+        }
+
+        Ok(best
+            .filter(|(distance, _, _)| *distance <= MAX_HASH_DISTANCE)
+            .map(|(_, index, frame)| (index, frame)))
+    }
+
+    /// Counts how many pixels of frame `index` fall into each of the 256 luma levels.
+    ///
+    /// Luma is computed with the BT.601 weights straight from the decoded buffer, without copying the frame into an image.
+    pub fn luma_histogram(&mut self, index: u64) -> Result<Option<[u32; 256]>> {
+        let frame = match self.get_frame_view(index)? {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
+
+        luma_histogram(&frame).map(Some)
+    }
+
+    /// Whether frame `index` is black, i.e. its average luma is at or below `threshold`; `None` if there is no frame there.
+    pub fn is_black_frame(&mut self, index: u64, threshold: u8) -> Result<Option<bool>> {
+        let histogram = match self.luma_histogram(index)? {
+            Some(histogram) => histogram,
+            None => return Ok(None),
+        };
+
+        let pixels: u64 = histogram.iter().map(|&count| count as u64).sum();
+
+        let total: u64 = histogram
+            .iter()
+            .enumerate()
+            .map(|(luma, &count)| luma as u64 * count as u64)
+            .sum();
+
+        Ok(Some(total <= threshold as u64 * pixels))
+    }
+
+    /// Finds the first and last frames that are not black, to trim black leaders and trailers off.
+    ///
+    /// Scans inward from both ends, so it stays quick when there is little to trim.
+    /// Fails if every frame is black.
+    pub fn trim_black_bounds(&mut self) -> Result<(u64, u64)> {
+        let len = self.len();
+
+        let mut first = None;
+
+        for index in 0..len {
+            match self.is_black_frame(index, BLACK_THRESHOLD)? {
+                Some(false) => {
+                    first = Some(index);
+                    break;
+                }
+                Some(true) => {}
+                None => break,
+            }
+        }
+
+        let first = first.ok_or(VidSeqError::AllBlack)?;
+
+        // the frame count may overshoot, so missing frames at the end are skipped rather than ending the scan
+        for index in (first..len).rev() {
+            if self.is_black_frame(index, BLACK_THRESHOLD)? == Some(false) {
+                return Ok((first, index));
+            }
+        }
+
+        Ok((first, first))
+    }
+
+    /// Per-pixel absolute difference between frames `i` and `j`, `None` if either of them is missing.
+    ///
+    /// The earlier frame is decoded first, so that getting to the later one only takes a forward seek.
+    pub fn frame_diff(&mut self, i: u64, j: u64) -> Result<Option<RgbImage>> {
+        let (first, second) = (i.min(j), i.max(j));
+
+        let first = match self.get_frame(first)? {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
+
+        let second = match self.get_frame(second)? {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
+
+        if first.dimensions() != second.dimensions() {
+            return Err(VidSeqError::ResolutionChanged {
+                from: first.dimensions(),
+                to: second.dimensions(),
+            });
+        }
+
+        let (width, height) = first.dimensions();
+
+        let diff = first
+            .into_raw()
+            .iter()
+            .zip(second.as_raw())
+            .map(|(a, b)| a.abs_diff(*b))
+            .collect();
+
+        RgbImage::from_raw(width, height, diff)
+            .ok_or_else(|| VidSeqError::InvalidFrame("image buffer was not sufficient".to_owned()))
+            .map(Some)
+    }
+
+    /// Walks through every decoded frame and checks its timestamp against the cadence of the frame rate,
+    /// to find out where index-based access will repeat or skip frames.
+    ///
+    /// Frames are identified by the index their timestamp maps to, see `timestamp_to_index`.
+    /// This decodes the whole video, and leaves the sequence positioned at its last frame.
+    pub fn analyze_continuity(&mut self) -> Result<ContinuityReport> {
+        let mut report = ContinuityReport::default();
+
+        // anything within half a frame of the expected spacing is just timestamp rounding
+        let max_delta = self.per_frame + self.per_frame / 2;
+
+        self.raw_seek(0)?;
+
+        let mut previous: Option<Duration> = None;
+
+        while let Some(pts) = self.preroll_pts()? {
+            if let Some(previous) = previous {
+                if pts <= previous {
+                    report.duplicates.push(self.timestamp_to_index(pts));
+                } else if pts - previous > max_delta {
+                    report.gaps.push((
+                        self.timestamp_to_index(previous),
+                        self.timestamp_to_index(pts),
+                    ));
+                }
+            }
+
+            self.current_index = self.displayed_index(pts);
+
+            previous = Some(pts);
+
+            self.step_buffer(pts)?;
+        }
+
+        Ok(report)
+    }
+}
@@ -0,0 +1,72 @@
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use image::RgbImage;
+
+use crate::{Result, VidSeqError, VideoSequence, VideoSequenceBuilder};
+
+/// A `VideoSequence` for async code, which does its blocking gstreamer work on tokio's blocking thread pool.
+///
+/// Every call locks the sequence for as long as it runs, so calls on one sequence still happen one after another;
+/// open several sequences to decode in parallel. Calls resolve to an error rather than hanging when the pipeline does,
+/// after the same timeouts as on `VideoSequence`. Dropping a future does not abort the call it started,
+/// it just finishes in the background.
+#[derive(Clone)]
+pub struct AsyncVideoSequence {
+    seq: Arc<Mutex<VideoSequence>>,
+}
+
+impl AsyncVideoSequence {
+    /// Opens a video file, see `VideoSequence::open`.
+    pub async fn open<P: Into<PathBuf>>(path: P) -> Result<Self> {
+        Self::open_with(path, VideoSequenceBuilder::new()).await
+    }
+
+    /// Opens a video file with the options in `builder`, see `VideoSequenceBuilder::open`.
+    pub async fn open_with<P: Into<PathBuf>>(
+        path: P,
+        builder: VideoSequenceBuilder,
+    ) -> Result<Self> {
+        let path = path.into();
+
+        let seq = tokio::task::spawn_blocking(move || builder.open(path)).await??;
+
+        Ok(seq.into())
+    }
+
+    /// See `VideoSequence::get_frame`.
+    pub async fn get_frame(&self, index: u64) -> Result<Option<RgbImage>> {
+        self.with(move |seq| seq.get_frame(index)).await?
+    }
+
+    /// See `VideoSequence::len`.
+    pub async fn len(&self) -> Result<u64> {
+        self.with(|seq| seq.len()).await
+    }
+
+    /// Runs `f` on the sequence on the blocking thread pool, for anything that has no async counterpart here.
+    pub async fn with<F, R>(&self, f: F) -> Result<R>
+    where
+        F: FnOnce(&mut VideoSequence) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let seq = self.seq.clone();
+
+        tokio::task::spawn_blocking(move || -> Result<R> {
+            let mut seq = seq.lock().map_err(|_| VidSeqError::Poisoned)?;
+
+            Ok(f(&mut seq))
+        })
+        .await?
+    }
+}
+
+impl From<VideoSequence> for AsyncVideoSequence {
+    fn from(seq: VideoSequence) -> Self {
+        Self {
+            seq: Arc::new(Mutex::new(seq)),
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use std::collections::{HashMap, VecDeque};
+
+use image::RgbImage;
+
+/// Decoded frames kept around for `VideoSequence::get_frame`, see `VideoSequenceBuilder::frame_cache`.
+///
+/// Evicts the least recently used frame once it holds `capacity` frames.
+pub(crate) struct FrameCache {
+    capacity: usize,
+    frames: HashMap<u64, RgbImage>,
+    /// Indices from least to most recently used.
+    order: VecDeque<u64>,
+}
+
+impl FrameCache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            frames: HashMap::with_capacity(capacity),
+            order: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub(crate) fn get(&mut self, index: u64) -> Option<&RgbImage> {
+        if self.frames.contains_key(&index) {
+            self.touch(index);
+        }
+
+        self.frames.get(&index)
+    }
+
+    pub(crate) fn insert(&mut self, index: u64, frame: RgbImage) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.frames.insert(index, frame).is_some() {
+            self.touch(index);
+
+            return;
+        }
+
+        self.order.push_back(index);
+
+        if self.order.len() > self.capacity {
+            if let Some(evicted) = self.order.pop_front() {
+                self.frames.remove(&evicted);
+            }
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.frames.clear();
+        self.order.clear();
+    }
+
+    /// Marks `index` as the most recently used.
+    fn touch(&mut self, index: u64) {
+        if let Some(position) = self.order.iter().position(|&i| i == index) {
+            self.order.remove(position);
+        }
+
+        self.order.push_back(index);
+    }
+}
@@ -0,0 +1,126 @@
+use std::{
+    sync::{
+        mpsc::{sync_channel, Receiver},
+        Arc, Condvar, Mutex,
+    },
+    thread,
+};
+
+use image::RgbImage;
+
+use crate::{Result, VideoSequence, CAT};
+
+/// How many decoded frames may wait in the channel before the decoder thread blocks.
+const CHANNEL_CAPACITY: usize = 4;
+
+/// Handle to a decoder thread started with `VideoSequence::spawn_decoder`.
+///
+/// Iterating it yields the decoded frames in order, followed by the error that stopped the thread if there was one;
+/// dropping it stops the thread.
+pub struct DecoderHandle {
+    receiver: Receiver<Result<(u64, RgbImage)>>,
+    paused: Arc<(Mutex<bool>, Condvar)>,
+}
+
+impl DecoderHandle {
+    /// Stops the thread from decoding further frames, the pipeline stays paused at its current position.
+    ///
+    /// Frames that were already decoded can still be received.
+    pub fn pause(&self) {
+        *self.paused.0.lock().unwrap() = true;
+    }
+
+    /// Continues decoding from the frame after the last one that was sent.
+    pub fn resume(&self) {
+        *self.paused.0.lock().unwrap() = false;
+
+        self.paused.1.notify_all();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.paused.0.lock().unwrap()
+    }
+}
+
+impl Iterator for DecoderHandle {
+    type Item = Result<(u64, RgbImage)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.receiver.recv().ok()
+    }
+}
+
+impl Drop for DecoderHandle {
+    fn drop(&mut self) {
+        // wake a paused thread, so it notices the receiver is gone and exits
+        self.resume();
+    }
+}
+
+impl VideoSequence {
+    /// Moves this sequence onto its own thread, which decodes every frame from the start and sends it down a bounded channel.
+    ///
+    /// The thread blocks while the channel is full or the handle is paused, and stops when the video ends,
+    /// when a frame fails to decode, or when the handle is dropped; the pipeline is torn down along with it.
+    /// A decoding failure is sent as the last item, so that it can be told apart from the end of the video.
+    pub fn spawn_decoder(self) -> DecoderHandle {
+        self.spawn(None)
+    }
+
+    /// Like `spawn_decoder`, but frames that fail to decode are logged as warnings and skipped, instead of stopping the thread.
+    ///
+    /// The thread still gives up after `max_consecutive_errors` failures in a row, as the rest of the video is likely unreadable then.
+    /// Warnings go to gstreamer's debug log under the `vidseq` category, e.g. with `GST_DEBUG=vidseq:2`.
+    pub fn spawn_decoder_skipping_errors(self, max_consecutive_errors: u32) -> DecoderHandle {
+        self.spawn(Some(max_consecutive_errors))
+    }
+
+    fn spawn(mut self, max_consecutive_errors: Option<u32>) -> DecoderHandle {
+        let (sender, receiver) = sync_channel(CHANNEL_CAPACITY);
+
+        let paused = Arc::new((Mutex::new(false), Condvar::new()));
+
+        let thread_paused = paused.clone();
+
+        thread::spawn(move || {
+            let mut errors = 0;
+
+            for index in 0..self.len() {
+                {
+                    let (lock, cvar) = &*thread_paused;
+
+                    let _guard = cvar
+                        .wait_while(lock.lock().unwrap(), |paused| *paused)
+                        .unwrap();
+                }
+
+                let frame = match self.get_frame(index) {
+                    Ok(Some(frame)) => frame,
+                    Ok(None) => break,
+                    Err(e) => match max_consecutive_errors {
+                        Some(max) if errors < max => {
+                            errors += 1;
+
+                            gstreamer::gst_warning!(CAT, "skipping frame {}: {}", index, e);
+
+                            continue;
+                        }
+                        _ => {
+                            let _ = sender.send(Err(e));
+
+                            break;
+                        }
+                    },
+                };
+
+                errors = 0;
+
+                if sender.send(Ok((index, frame))).is_err() {
+                    break;
+                }
+            }
+        });
+
+        DecoderHandle { receiver, paused }
+    }
+}
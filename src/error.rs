@@ -0,0 +1,98 @@
+use std::{path::PathBuf, time::Duration};
+
+use thiserror::Error;
+
+/// Result of every fallible operation in this crate.
+pub type Result<T, E = VidSeqError> = std::result::Result<T, E>;
+
+/// Everything that can go wrong while opening a video or getting frames out of it.
+#[derive(Debug, Error)]
+pub enum VidSeqError {
+    #[error("video is {width}x{height}, exceeding the maximum of {max_width}x{max_height}")]
+    ResolutionTooLarge {
+        width: u32,
+        height: u32,
+        max_width: u32,
+        max_height: u32,
+    },
+    #[error("video resolution changed from {}x{} to {}x{}", from.0, from.1, to.0, to.1)]
+    ResolutionChanged { from: (u32, u32), to: (u32, u32) },
+    #[error("frame is in {actual} format, while the video was opened as {expected}")]
+    FormatMismatch { expected: String, actual: String },
+    #[error("file {} does not exist", path.display())]
+    NotFound { path: PathBuf },
+    #[error("file has no video stream")]
+    NoVideoStream,
+    /// A gstreamer element is not installed, usually because the plugin that provides it is missing.
+    #[error("gstreamer element {factory} is missing")]
+    MissingElement { factory: String },
+    #[error("frames in {format} format are not supported")]
+    UnsupportedFormat { format: String },
+    #[error("live sources are not supported")]
+    LiveSource,
+    #[error("frame {index} is out of range for a video of {frames} frames")]
+    OutOfRange { index: u64, frames: u64 },
+    /// The pipeline did not finish a state change, seek or step in time.
+    #[error("timed out after {timeout:?} waiting for the pipeline")]
+    Timeout { timeout: Duration },
+    /// The frame rate of the video is negative, or too high to seek by.
+    #[error("frame rate {num}/{denom} is not supported for seeking")]
+    UnsupportedFrameRate { num: i32, denom: i32 },
+    #[error("could not determine the duration of the video")]
+    UnknownDuration,
+    /// The decoder asked for with `VideoSequenceBuilder::decoder` did not get picked for the video.
+    #[error("decoder {expected} was not used for this video, got {}", actual.as_deref().unwrap_or("none"))]
+    DecoderNotUsed {
+        expected: String,
+        actual: Option<String>,
+    },
+    #[error("the pipeline did not handle the seek event")]
+    SeekNotHandled,
+    #[error("the pipeline did not handle the step event")]
+    StepNotHandled,
+    #[error("sample has no caps")]
+    NoCaps,
+    #[error("sample has no buffer")]
+    NoBuffer,
+    /// A decoded frame could not be read, e.g. because its buffer is smaller than its caps say.
+    #[error("{0}")]
+    InvalidFrame(String),
+    /// An argument is out of the range it has to be in; the message says which one.
+    #[error("{0}")]
+    InvalidArgument(String),
+    /// A batch or image this large would not fit in memory.
+    #[error("{what} is too large to fit in memory")]
+    TooLarge { what: &'static str },
+    /// Every frame is black, see `VideoSequence::trim_black_bounds`.
+    #[error("video has no frames that are not black")]
+    AllBlack,
+    /// Counting the frames failed before, see `VideoSequence::count_frames`.
+    #[error("counting the frames failed earlier")]
+    CountFailed,
+    /// The pipeline posted an error, e.g. because the file could not be demuxed or decoded.
+    #[error(transparent)]
+    Pipeline(#[from] gstreamer::glib::Error),
+    #[error(transparent)]
+    StateChange(#[from] gstreamer::StateChangeError),
+    #[error(transparent)]
+    Flow(#[from] gstreamer::FlowError),
+    /// A gstreamer call failed, e.g. linking elements or setting a property.
+    #[error(transparent)]
+    Glib(#[from] gstreamer::glib::BoolError),
+    /// The pipeline was not put together the way it should have been, e.g. it has no bus; this points at a bug.
+    #[error("{0}")]
+    Internal(String),
+    /// A panic happened while the sequence was in use.
+    #[error("sequence was poisoned by a panic")]
+    Poisoned,
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Image(#[from] image::ImageError),
+    #[cfg(feature = "zip")]
+    #[error(transparent)]
+    Zip(#[from] zip::result::ZipError),
+    #[cfg(feature = "tokio")]
+    #[error(transparent)]
+    Join(#[from] tokio::task::JoinError),
+}
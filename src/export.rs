@@ -0,0 +1,267 @@
+use image::{imageops, DynamicImage, ImageFormat, ImageOutputFormat, RgbImage};
+
+use std::{path::Path, time::Duration};
+
+use crate::{FrameView, OrientationMode, Result, VidSeqError, VideoSequence};
+
+/// JPEG quality of the thumbnails made by `VideoSequence::thumbnail_track`.
+const THUMBNAIL_QUALITY: u8 = 75;
+
+/// JPEG quality of the frames written by `VideoSequence::save_frame`.
+const SAVE_QUALITY: u8 = 90;
+
+/// Encodes `img` in `format`, entirely in memory.
+///
+/// `quality` goes from 1 to 100 and only matters for lossy formats, which is just JPEG for now.
+pub fn encode_image(img: RgbImage, format: ImageFormat, quality: u8) -> Result<Vec<u8>> {
+    let output = match format {
+        ImageFormat::Jpeg => ImageOutputFormat::Jpeg(quality.clamp(1, 100)),
+        format => format.into(),
+    };
+
+    let mut buf = Vec::new();
+
+    DynamicImage::ImageRgb8(img).write_to(&mut buf, output)?;
+
+    Ok(buf)
+}
+
+/// Inserts an EXIF segment holding just the `Orientation` tag into an encoded JPEG, right after its header segments.
+fn with_exif_orientation(jpeg: Vec<u8>, orientation: u16) -> Result<Vec<u8>> {
+    if !jpeg.starts_with(&[0xFF, 0xD8]) {
+        return Err(VidSeqError::Internal("not a JPEG image".to_owned()));
+    }
+
+    let mut exif = Vec::with_capacity(36);
+
+    // APP1 marker, and the length of the segment without the marker
+    exif.extend_from_slice(&[0xFF, 0xE1, 0x00, 0x22]);
+    exif.extend_from_slice(b"Exif\0\0");
+    // big-endian TIFF header, with the first IFD right after it
+    exif.extend_from_slice(&[b'M', b'M', 0x00, 0x2A, 0x00, 0x00, 0x00, 0x08]);
+    // a single entry: tag 0x0112 (Orientation), type SHORT, count 1, value padded to 4 bytes
+    exif.extend_from_slice(&[0x00, 0x01, 0x01, 0x12, 0x00, 0x03, 0x00, 0x00, 0x00, 0x01]);
+    exif.extend_from_slice(&orientation.to_be_bytes());
+    exif.extend_from_slice(&[0x00, 0x00]);
+    // no further IFDs
+    exif.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]);
+
+    // the JFIF APP0 segment has to stay first, if there is one
+    let mut at = 2;
+
+    if jpeg.get(2..4) == Some(&[0xFF, 0xE0]) {
+        let length = jpeg
+            .get(4..6)
+            .map(|length| u16::from_be_bytes([length[0], length[1]]) as usize)
+            .ok_or_else(|| VidSeqError::Internal("JPEG image is truncated".to_owned()))?;
+
+        at += 2 + length;
+    }
+
+    if at > jpeg.len() {
+        return Err(VidSeqError::Internal("JPEG image is truncated".to_owned()));
+    }
+
+    let mut out = Vec::with_capacity(jpeg.len() + exif.len());
+
+    out.extend_from_slice(&jpeg[..at]);
+    out.extend_from_slice(&exif);
+    out.extend_from_slice(&jpeg[at..]);
+
+    Ok(out)
+}
+
+impl VideoSequence {
+    /// Writes frame `index` to `path`, in the image format that its extension stands for; `false` if there is no frame there.
+    ///
+    /// With `OrientationMode::Exif`, JPEGs get the orientation of the video as EXIF metadata.
+    pub fn save_frame<P: AsRef<Path>>(&mut self, index: u64, path: P) -> Result<bool> {
+        let format = ImageFormat::from_path(path.as_ref())?;
+
+        let mut bytes = match self.get_frame_encoded(index, format, SAVE_QUALITY)? {
+            Some(bytes) => bytes,
+            None => return Ok(false),
+        };
+
+        if let (ImageFormat::Jpeg, OrientationMode::Exif, Some(orientation)) =
+            (format, self.options.orientation_mode, self.orientation)
+        {
+            bytes = with_exif_orientation(bytes, orientation.exif())?;
+        }
+
+        std::fs::write(path, bytes)?;
+
+        Ok(true)
+    }
+
+    /// Like `get_frame`, but encodes the frame in `format`, see `encode_image`.
+    pub fn get_frame_encoded(
+        &mut self,
+        index: u64,
+        format: ImageFormat,
+        quality: u8,
+    ) -> Result<Option<Vec<u8>>> {
+        self.get_frame(index)?
+            .map(|frame| encode_image(frame, format, quality))
+            .transpose()
+    }
+
+    /// Makes a JPEG thumbnail of `width` by `height` every `interval`, as used by players to preview where scrubbing will go.
+    ///
+    /// For speed, every thumbnail comes from the keyframe nearest to its timestamp, and gstreamer does the scaling.
+    /// Thumbnails are paired with the timestamp they were requested at; the sequence is returned to its previous frame afterwards.
+    /// Fails with `VidSeqError::InvalidArgument` on a sequence with GL output, which cannot be scaled.
+    pub fn thumbnail_track(
+        &mut self,
+        interval: Duration,
+        width: u32,
+        height: u32,
+    ) -> Result<Vec<(Duration, Vec<u8>)>> {
+        if interval.is_zero() {
+            return Err(VidSeqError::InvalidArgument(
+                "thumbnail interval cannot be zero".to_owned(),
+            ));
+        }
+
+        if width == 0 || height == 0 {
+            return Err(VidSeqError::InvalidArgument(
+                "thumbnails need a size".to_owned(),
+            ));
+        }
+
+        let position = self.current_index;
+        let output_size = self.inner.output_size;
+
+        self.inner.scale_output(Some((width, height)))?;
+
+        let thumbnails = self.collect_thumbnails(interval);
+
+        // also restore the original scale, even if a thumbnail failed
+        self.inner.scale_output(output_size)?;
+        self.raw_seek(position)?;
+
+        thumbnails
+    }
+
+    fn collect_thumbnails(&mut self, interval: Duration) -> Result<Vec<(Duration, Vec<u8>)>> {
+        use gstreamer::SeekFlags;
+
+        let mut thumbnails = Vec::new();
+        let mut timestamp = Duration::ZERO;
+
+        while timestamp < self.duration {
+            self.seek_to_time_with(timestamp, SeekFlags::KEY_UNIT | SeekFlags::SNAP_NEAREST)?;
+
+            if self.inner.eos {
+                break;
+            }
+
+            // pulled straight from the sink, as these frames are not the size of the video
+            let sample = self.inner.appsink.pull_preroll()?;
+
+            let frame = FrameView::from_sample(
+                &sample,
+                self.options.crop_to_clean_aperture,
+                self.options.system_memory,
+            )?;
+
+            thumbnails.push((
+                timestamp,
+                encode_image(frame.to_image()?, ImageFormat::Jpeg, THUMBNAIL_QUALITY)?,
+            ));
+
+            timestamp += interval;
+        }
+
+        Ok(thumbnails)
+    }
+
+    /// Makes a grid of `cols` by `rows` frames, evenly spread across the video and each scaled to `cell`, to summarize it in one image.
+    ///
+    /// Frames are gathered in a single forward pass, see `get_frames`. Cells are filled in row-major order;
+    /// those left over when the video has fewer frames than cells, or ends early, stay black.
+    pub fn contact_sheet(&mut self, cols: u32, rows: u32, cell: (u32, u32)) -> Result<RgbImage> {
+        let (cell_width, cell_height) = cell;
+
+        if cols == 0 || rows == 0 || cell_width == 0 || cell_height == 0 {
+            return Err(VidSeqError::InvalidArgument(
+                "contact sheet needs at least one cell, with a size".to_owned(),
+            ));
+        }
+
+        let len = self.len();
+        let count = (cols as u64 * rows as u64).min(len);
+
+        let indices: Vec<u64> = (0..count).map(|i| i * len / count).collect();
+
+        let (width, height) = cols
+            .checked_mul(cell_width)
+            .zip(rows.checked_mul(cell_height))
+            .filter(|&(width, height)| {
+                (width as usize)
+                    .checked_mul(height as usize)
+                    .and_then(|pixels| pixels.checked_mul(3))
+                    .is_some()
+            })
+            .ok_or(VidSeqError::TooLarge {
+                what: "contact sheet",
+            })?;
+
+        let mut sheet = RgbImage::new(width, height);
+
+        for (i, (_, frame)) in self.get_frames(&indices)?.into_iter().enumerate() {
+            let thumbnail = imageops::resize(
+                &frame,
+                cell_width,
+                cell_height,
+                imageops::FilterType::Triangle,
+            );
+
+            let (col, row) = (i as u32 % cols, i as u32 / cols);
+
+            imageops::replace(&mut sheet, &thumbnail, col * cell_width, row * cell_height);
+        }
+
+        Ok(sheet)
+    }
+
+    /// Encodes the frames at `indices` in `format` and writes them into a zip archive, without going through the filesystem.
+    ///
+    /// Entries are named after their zero-padded index, e.g. `00000042.png`, and stored as-is since images are already compressed.
+    /// Indices without a frame are left out; returns how many frames made it into the archive.
+    #[cfg(feature = "zip")]
+    pub fn export_frames_zip<W: std::io::Write + std::io::Seek>(
+        &mut self,
+        indices: &[u64],
+        out: W,
+        format: ImageFormat,
+        quality: u8,
+    ) -> Result<usize> {
+        use std::io::Write;
+
+        use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+        let extension = format.extensions_str().first().copied().unwrap_or("bin");
+
+        let options = FileOptions::default().compression_method(CompressionMethod::Stored);
+
+        let mut zip = ZipWriter::new(out);
+        let mut count = 0;
+
+        for &index in indices {
+            let bytes = match self.get_frame_encoded(index, format, quality)? {
+                Some(bytes) => bytes,
+                None => continue,
+            };
+
+            zip.start_file(format!("{:08}.{}", index, extension), options)?;
+            zip.write_all(&bytes)?;
+
+            count += 1;
+        }
+
+        zip.finish()?;
+
+        Ok(count)
+    }
+}
@@ -0,0 +1,130 @@
+use gstreamer::{
+    prelude::{Cast, GstBinExtManual},
+    traits::ElementExt,
+    ElementFactory,
+};
+use gstreamer_gl::prelude::{ContextGLExt, VideoFrameGLExt};
+use gstreamer_video::{video_frame::Readable, VideoFrame};
+
+pub use gstreamer_gl::{GLContext, GLDisplay};
+
+use crate::{Result, VidSeqError, VideoSequence};
+
+/// The GL display and context that decoded frames get uploaded into.
+#[derive(Debug, Clone)]
+pub(crate) struct GlOutput {
+    display: GLDisplay,
+    context: GLContext,
+}
+
+impl GlOutput {
+    pub(crate) fn new(display: GLDisplay, context: GLContext) -> Self {
+        Self { display, context }
+    }
+
+    /// Caps for RGBA frames that live in GL textures.
+    pub(crate) fn caps() -> gstreamer::Caps {
+        gstreamer::Caps::builder("video/x-raw")
+            .features(&["memory:GLMemory"])
+            .field("format", "RGBA")
+            .field("texture-target", "2D")
+            .build()
+    }
+
+    /// Wraps `appsink` into a bin that uploads and converts frames to GL textures before they reach it.
+    pub(crate) fn video_sink(
+        &self,
+        appsink: &gstreamer_app::AppSink,
+    ) -> Result<gstreamer::Element> {
+        let bin = gstreamer::Bin::new(None);
+
+        let upload = ElementFactory::make("glupload", None)?;
+        let convert = ElementFactory::make("glcolorconvert", None)?;
+
+        bin.add_many(&[&upload, &convert, appsink.upcast_ref()])?;
+        gstreamer::Element::link_many(&[&upload, &convert, appsink.upcast_ref()])?;
+
+        let pad = upload
+            .static_pad("sink")
+            .ok_or_else(|| VidSeqError::Internal("glupload has no sink pad".to_owned()))?;
+
+        bin.add_pad(&gstreamer::GhostPad::with_target(Some("sink"), &pad)?)?;
+
+        Ok(bin.upcast())
+    }
+
+    /// Hands the display and context to `pipeline`, which passes them on to any GL element that asks for them.
+    pub(crate) fn share_with(&self, pipeline: &gstreamer::Element) {
+        let display = gstreamer::Context::new("gst.gl.GLDisplay", true);
+        display.set_gl_display(&self.display);
+
+        pipeline.set_context(&display);
+
+        let mut app = gstreamer::Context::new("gst.gl.app_context", true);
+
+        if let Some(app) = app.get_mut() {
+            app.structure_mut().set("context", &self.context);
+        }
+
+        pipeline.set_context(&app);
+    }
+}
+
+/// A decoded frame that stays on the GPU as a GL texture.
+///
+/// The texture belongs to gstreamer and is only valid while this is alive,
+/// after which it may be reused for later frames.
+pub struct GlFrame {
+    frame: VideoFrame<Readable>,
+}
+
+impl GlFrame {
+    /// Name of the RGBA `GL_TEXTURE_2D` that holds this frame, `None` if gstreamer did not hand out a texture for it.
+    pub fn texture_id(&self) -> Option<u32> {
+        // an RGBA frame with a texture-target of 2D always has a single texture
+        self.frame.texture_id(0)
+    }
+
+    pub fn width(&self) -> u32 {
+        self.frame.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.frame.height()
+    }
+}
+
+impl VideoSequence {
+    /// Like `get_frame`, but leaves the frame on the GPU, see `VideoSequenceBuilder::gl_context`.
+    pub fn get_gl_frame(&mut self, index: u64) -> Result<Option<GlFrame>> {
+        let gl = self.options.gl.clone().ok_or_else(|| {
+            VidSeqError::InvalidArgument("video was not opened with a GL context".to_owned())
+        })?;
+
+        self.seek(index)?;
+
+        let sample = match self.preroll_sample()? {
+            Some(sample) => sample,
+            None => return Ok(None),
+        };
+
+        let caps = sample.caps().ok_or(VidSeqError::NoCaps)?;
+
+        let buffer = match sample.buffer_owned() {
+            Some(buffer) => buffer,
+            None => return Ok(None),
+        };
+
+        // the texture may still be written to by gstreamer's GL thread
+        if let Some(sync) = buffer.meta::<gstreamer_gl::GLSyncMeta>() {
+            sync.wait(&gl.context);
+        }
+
+        let info = gstreamer_video::VideoInfo::from_caps(caps)?;
+
+        let frame = VideoFrame::from_buffer_readable_gl(buffer, &info)
+            .map_err(|_| VidSeqError::InvalidFrame("frame is not in GL memory".to_owned()))?;
+
+        Ok(Some(GlFrame { frame }))
+    }
+}
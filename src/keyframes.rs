@@ -0,0 +1,168 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use gstreamer::{
+    prelude::{Cast, GstBinExtManual, ObjectExt, PadExtManual},
+    traits::{ElementExt, GstBinExt, PadExt},
+    ElementFactory, EventView, MessageView, PadProbeData, PadProbeReturn, PadProbeType,
+};
+
+use crate::{
+    check_or_init_gst, clock_time, missing_element, Result, StopOnDrop, VidSeqError, VideoSequence,
+    DEFAULT_TIMEOUT,
+};
+
+impl VideoSequence {
+    /// Index and timestamp of every keyframe, which is where a GOP starts and where the video can be cut without re-encoding.
+    ///
+    /// This runs a separate pass over the file that only parses the video stream without decoding it, which is fast,
+    /// but still reads the whole file. Indices follow the timestamps, the same way seeking does.
+    pub fn gop_boundaries(&mut self) -> Result<Vec<(u64, Duration)>> {
+        let mut keyframes = keyframe_timestamps(&self.uri, DEFAULT_TIMEOUT)?;
+
+        keyframes.sort_unstable();
+        keyframes.dedup();
+
+        Ok(keyframes
+            .into_iter()
+            .map(|timestamp| (self.displayed_index(timestamp), timestamp))
+            .collect())
+    }
+}
+
+/// Parses the video at `uri` without decoding it, collecting the timestamps of its keyframes in stream order.
+///
+/// Fails with `VidSeqError::Timeout` when parsing stalls for `timeout`.
+fn keyframe_timestamps(uri: &str, timeout: Duration) -> Result<Vec<Duration>> {
+    check_or_init_gst()?;
+
+    let pipeline = gstreamer::Pipeline::new(None);
+
+    let source =
+        ElementFactory::make("urisourcebin", None).map_err(|_| missing_element("urisourcebin"))?;
+    let parse = ElementFactory::make("parsebin", None).map_err(|_| missing_element("parsebin"))?;
+
+    source.set_property("uri", uri)?;
+
+    pipeline.add_many(&[&source, &parse])?;
+
+    let parse_weak = parse.downgrade();
+
+    source.connect_pad_added(move |_, pad| {
+        if let Some(sink) = parse_weak
+            .upgrade()
+            .and_then(|parse| parse.static_pad("sink"))
+        {
+            let _ = pad.link(&sink);
+        }
+    });
+
+    let keyframes = Arc::new(Mutex::new(Vec::new()));
+    let parsed = Arc::new(AtomicU64::new(0));
+
+    let pipeline_weak = pipeline.downgrade();
+    let found = keyframes.clone();
+    let progress = parsed.clone();
+
+    // every parsed stream needs to end somewhere, but only the video one is inspected
+    parse.connect_pad_added(move |_, pad| {
+        let pipeline = match pipeline_weak.upgrade() {
+            Some(pipeline) => pipeline,
+            None => return,
+        };
+
+        let sink = match ElementFactory::make("fakesink", None) {
+            Ok(sink) => sink,
+            Err(_) => return,
+        };
+
+        if pipeline.add(&sink).is_err() {
+            return;
+        }
+
+        let _ = sink.sync_state_with_parent();
+
+        let is_video = pad
+            .current_caps()
+            .and_then(|caps| caps.structure(0).map(|s| s.name().starts_with("video/")))
+            .unwrap_or(false);
+
+        if is_video {
+            let found = found.clone();
+            let progress = progress.clone();
+
+            pad.add_probe(PadProbeType::BUFFER, move |pad, info| {
+                progress.fetch_add(1, Ordering::Relaxed);
+
+                if let Some(PadProbeData::Buffer(buffer)) = &info.data {
+                    if !buffer.flags().contains(gstreamer::BufferFlags::DELTA_UNIT) {
+                        if let Some(pts) = buffer.pts() {
+                            found.lock().unwrap().push(stream_time(pad, pts));
+                        }
+                    }
+                }
+
+                PadProbeReturn::Ok
+            });
+        }
+
+        if let Some(sink) = sink.static_pad("sink") {
+            let _ = pad.link(&sink);
+        }
+    });
+
+    let _stop = StopOnDrop(pipeline.clone().upcast());
+
+    pipeline.set_state(gstreamer::State::Playing)?;
+
+    let bus = pipeline
+        .bus()
+        .ok_or_else(|| VidSeqError::Internal("pipeline has no bus".to_owned()))?;
+
+    // long files take a while to parse, so only give up once no buffer arrived for `timeout`
+    let mut seen = 0;
+
+    let msg = loop {
+        let msg = bus.timed_pop_filtered(
+            Some(clock_time(timeout)?),
+            &[gstreamer::MessageType::Eos, gstreamer::MessageType::Error],
+        );
+
+        let now = parsed.load(Ordering::Relaxed);
+
+        if msg.is_some() || now == seen {
+            break msg;
+        }
+
+        seen = now;
+    };
+
+    match msg.as_ref().map(|msg| msg.view()) {
+        Some(MessageView::Error(err)) => return Err(VidSeqError::from(err.error())),
+        Some(_) => {}
+        None => return Err(VidSeqError::Timeout { timeout }),
+    }
+
+    let keyframes = keyframes.lock().unwrap().clone();
+
+    Ok(keyframes)
+}
+
+/// Converts a buffer timestamp on `pad` into stream time, using the segment that is currently in effect there.
+fn stream_time(pad: &gstreamer::Pad, pts: gstreamer::ClockTime) -> Duration {
+    pad.sticky_event(gstreamer::EventType::Segment, 0)
+        .and_then(|event| match event.view() {
+            EventView::Segment(segment) => segment
+                .segment()
+                .downcast_ref::<gstreamer::ClockTime>()
+                .and_then(|segment| segment.to_stream_time(pts)),
+            _ => None,
+        })
+        .unwrap_or(pts)
+        .into()
+}
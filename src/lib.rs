@@ -1,284 +1,3603 @@
-use std::{path::Path, sync::Once, time::Duration};
+use std::{
+    cell::Cell,
+    collections::HashMap,
+    ops::{Deref, DerefMut},
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, Once,
+    },
+    time::{Duration, Instant},
+};
 
 use gstreamer::{
-    prelude::{Cast, ElementExtManual, ObjectExt},
+    prelude::{Cast, ElementExtManual, GstObjectExt, ObjectExt, PadExtManual},
     traits::ElementExt,
-    ElementFactory, MessageView,
+    ElementFactory, EventView, MessageView, PadProbeData, PadProbeReturn, PadProbeType, QueryView,
 };
-use image::RgbImage;
+use image::{DynamicImage, GrayImage, ImageBuffer, Rgb, RgbImage, RgbaImage};
+
+mod analysis;
+#[cfg(feature = "tokio")]
+mod async_seq;
+mod cache;
+mod decoder;
+mod error;
+mod export;
+#[cfg(feature = "gl")]
+mod gl;
+mod keyframes;
+mod shared;
+
+pub use analysis::{average_hash, ContinuityReport};
+#[cfg(feature = "tokio")]
+pub use async_seq::AsyncVideoSequence;
+pub use decoder::DecoderHandle;
+pub use error::{Result, VidSeqError};
+pub use export::encode_image;
+#[cfg(feature = "gl")]
+pub use gl::{GLContext, GLDisplay, GlFrame};
+pub use shared::SharedVideoSequence;
 
 static GST_INIT: Once = Once::new();
 
+/// Debug category for messages logged through gstreamer, enable with e.g. `GST_DEBUG=vidseq:4`.
+static CAT: gstreamer::glib::once_cell::sync::Lazy<gstreamer::DebugCategory> =
+    gstreamer::glib::once_cell::sync::Lazy::new(|| {
+        gstreamer::DebugCategory::new(
+            "vidseq",
+            gstreamer::DebugColorFlags::empty(),
+            Some("Video frame extraction"),
+        )
+    });
+
+/// How long to wait for the pipeline to settle after a state change, seek or step.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long to wait for a step near the end of the video, where the next frame may not exist.
+const DEFAULT_EOS_TIMEOUT: Duration = Duration::from_millis(500);
+
 /// This toggles a library-internal flag that gstreamer has already been initiated.
 pub fn assume_gst_init() {
     GST_INIT.call_once(|| {})
 }
 
-fn check_or_init_gst() {
-    GST_INIT.call_once(|| gstreamer::init().expect("failed to initialize gst"))
+/// Initializes gstreamer (unless that already happened) and registers the plugins found in `paths`.
+///
+/// This allows shipping gstreamer plugins alongside an application, instead of relying on a system-wide install.
+/// It can be combined with `assume_gst_init`, and can be called again later on; videos opened afterwards can use the registered plugins.
+pub fn init_with_plugin_paths(paths: &[&Path]) -> Result<()> {
+    check_or_init_gst()?;
+
+    let registry = gstreamer::Registry::get();
+
+    for path in paths {
+        if !path.is_dir() {
+            return Err(VidSeqError::InvalidArgument(format!(
+                "plugin path {} is not a directory",
+                path.display()
+            )));
+        }
+
+        registry.scan_path(path);
+    }
+
+    Ok(())
+}
+
+/// Initializes gstreamer (unless that already happened) and sets how verbose its debug log is.
+///
+/// `level` goes from 0 (nothing) through 1 (errors), 2 (warnings), 4 (info) and 5 (debug) up to 9 (everything), like `GST_DEBUG`.
+/// With `categories`, a comma-separated list such as `"vidseq,videodecoder,*demux"`, only those get `level` and the rest is left as is;
+/// otherwise it applies to every category.
+/// The log goes to stderr, unless it is forwarded to the `log` crate with `forward_gst_log`.
+pub fn set_gst_debug(level: u32, categories: Option<&str>) -> Result<()> {
+    if level > 9 {
+        return Err(VidSeqError::InvalidArgument(format!(
+            "debug level {} is out of range",
+            level
+        )));
+    }
+
+    check_or_init_gst()?;
+
+    let list = match categories {
+        Some(categories) => categories
+            .split(',')
+            .map(|category| format!("{}:{}", category.trim(), level))
+            .collect::<Vec<_>>()
+            .join(","),
+        None => level.to_string(),
+    };
+
+    gstreamer::debug_set_active(true);
+    gstreamer::debug_set_threshold_from_string(&list, categories.is_none());
+
+    Ok(())
+}
+
+/// Initializes gstreamer (unless that already happened) and sends its debug log to the `log` crate instead of stderr.
+///
+/// Messages are logged with their gstreamer category as the target, how many there are still depends on `set_gst_debug`.
+#[cfg(feature = "log")]
+pub fn forward_gst_log() -> Result<()> {
+    check_or_init_gst()?;
+
+    gstreamer::debug_remove_default_log_function();
+
+    gstreamer::debug_add_log_function(
+        |category, level, file, _function, line, _object, message| {
+            let level = match level {
+                gstreamer::DebugLevel::Error => log::Level::Error,
+                gstreamer::DebugLevel::Warning | gstreamer::DebugLevel::Fixme => log::Level::Warn,
+                gstreamer::DebugLevel::Info => log::Level::Info,
+                gstreamer::DebugLevel::Debug => log::Level::Debug,
+                _ => log::Level::Trace,
+            };
+
+            if let Some(message) = message.get() {
+                log::log!(target: category.name(), level, "{}:{}: {}", file, line, message);
+            }
+        },
+    );
+
+    Ok(())
+}
+
+fn check_or_init_gst() -> Result<()> {
+    let mut result = Ok(());
+
+    GST_INIT.call_once(|| result = gstreamer::init());
+
+    Ok(result?)
 }
 
 struct VideoSequenceInner {
     pipeline: gstreamer::Element,
     appsink: gstreamer_app::AppSink,
+
+    toc: Option<gstreamer::Toc>,
+    eos: bool,
+
+    /// Last segment event that reached the appsink.
+    segment: Arc<Mutex<Option<gstreamer::Segment>>>,
+
+    /// Size the output is scaled to, if any.
+    output_size: Option<(u32, u32)>,
+    pixel_format: PixelFormat,
+    /// Whether frames are uploaded into GL textures, see `VideoSequenceBuilder::gl_context`.
+    gl_output: bool,
 }
 
 impl VideoSequenceInner {
-    fn set_state_with_timeout(
-        &self,
-        state: gstreamer::State,
-        timeout: Duration,
-    ) -> anyhow::Result<()> {
-        match self.pipeline.set_state(state)? {
+    /// Builds the playbin pipeline for `uri`, with its video routed into an RGB appsink.
+    fn new(uri: &str, options: &VideoSequenceBuilder) -> Result<Self> {
+        check_or_init_gst()?;
+
+        if options.force_software_decode {
+            derank_hardware_decoders();
+        }
+
+        if let Some(decoder) = &options.decoder {
+            prefer_decoder(decoder)?;
+        }
+
+        let pipeline = ElementFactory::make(options.playbin_factory(), None).map_err(|_| {
+            VidSeqError::MissingElement {
+                factory: options.playbin_factory().to_owned(),
+            }
+        })?;
+
+        pipeline.set_property("uri", uri)?;
+        pipeline.set_property(
+            "audio-sink",
+            ElementFactory::make("fakesink", Some("fakeaudio"))?,
+        )?;
+
+        if options.orientation_mode == OrientationMode::Apply {
+            use gstreamer::prelude::GObjectExtManualGst;
+
+            let flip = ElementFactory::make("videoflip", None)
+                .map_err(|_| missing_element("videoflip"))?;
+
+            // follows the image-orientation tag of the stream
+            flip.set_property_from_str("video-direction", "auto");
+
+            pipeline.set_property("video-filter", flip)?;
+        }
+
+        let appsink = ElementFactory::make("appsink", None)
+            .map_err(|_| missing_element("appsink"))?
+            .dynamic_cast::<gstreamer_app::AppSink>()
+            .map_err(|_| VidSeqError::Internal("appsink element is not an AppSink".to_owned()))?;
+
+        #[cfg(feature = "gl")]
+        if let Some(gl) = &options.gl {
+            appsink.set_property("caps", gl::GlOutput::caps())?;
+            pipeline.set_property("video-sink", gl.video_sink(&appsink)?)?;
+
+            gl.share_with(&pipeline);
+        }
+
+        let gl_output = appsink.parent().is_some();
+
+        if !gl_output {
+            appsink.set_property("caps", output_caps(options.pixel_format, None))?;
+
+            if options.system_memory {
+                pipeline.set_property("video-sink", converting_sink(&appsink)?)?;
+            } else {
+                pipeline.set_property("video-sink", appsink.clone())?;
+            }
+        }
+
+        let segment = Arc::new(Mutex::new(None));
+
+        if let Some(pad) = appsink.static_pad("sink") {
+            let segment = segment.clone();
+
+            let on_caps_change = options.on_caps_change.clone();
+            let last_caps = Mutex::new(None);
+
+            pad.add_probe(PadProbeType::EVENT_DOWNSTREAM, move |_, info| {
+                if let Some(PadProbeData::Event(ev)) = &info.data {
+                    match ev.view() {
+                        EventView::Segment(seg) => {
+                            *segment.lock().unwrap() = Some(seg.segment().clone());
+                        }
+                        EventView::Caps(caps) => {
+                            if let Some(callback) = &on_caps_change {
+                                let caps = caps.caps_owned();
+                                let mut last_caps = last_caps.lock().unwrap();
+
+                                // caps can be sent again unchanged, e.g. after a flushing seek
+                                if last_caps.as_ref() != Some(&caps) {
+                                    (callback.0.lock().unwrap())(&caps);
+
+                                    *last_caps = Some(caps);
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+
+                PadProbeReturn::Ok
+            });
+
+            let buffer_pool = options.buffer_pool;
+
+            pad.add_probe(PadProbeType::QUERY_DOWNSTREAM, move |_, info| {
+                if let Some(PadProbeData::Query(query)) = &mut info.data {
+                    if let QueryView::Allocation(mut allocation) = query.view_mut() {
+                        // have decoders hand over the full coded frame along with its clean aperture,
+                        // instead of cropping it themselves, so that the aperture can be reported
+                        allocation.add_allocation_meta::<gstreamer_video::VideoCropMeta>(None);
+
+                        let layout = FrameLayout::from_caps(allocation.get().0);
+
+                        if let (Some(max_buffers), Ok(layout)) = (buffer_pool, layout) {
+                            allocation.add_allocation_pool(
+                                Some(&gstreamer::BufferPool::new()),
+                                (layout.stride * layout.height) as u32,
+                                1,
+                                max_buffers,
+                            );
+
+                            return PadProbeReturn::Handled;
+                        }
+                    }
+                }
+
+                PadProbeReturn::Ok
+            });
+        }
+
+        Ok(Self {
+            pipeline,
+            appsink,
+            toc: None,
+            eos: false,
+            segment,
+            output_size: None,
+            pixel_format: options.pixel_format,
+            gl_output,
+        })
+    }
+
+    /// Has the pipeline scale its output to `(width, height)`, or not at all, takes effect on the next flushing seek.
+    ///
+    /// GL output cannot be scaled, as that would replace its GL memory caps with system memory ones.
+    fn scale_output(&mut self, size: Option<(u32, u32)>) -> Result<()> {
+        if self.gl_output {
+            return match size {
+                Some(_) => Err(VidSeqError::InvalidArgument(
+                    "frames cannot be scaled with GL output".to_owned(),
+                )),
+                None => Ok(()),
+            };
+        }
+
+        self.appsink
+            .set_property("caps", output_caps(self.pixel_format, size))?;
+
+        self.output_size = size;
+
+        if let Some(pad) = self.appsink.static_pad("sink") {
+            pad.push_event(gstreamer::event::Reconfigure::new());
+        }
+
+        Ok(())
+    }
+
+    /// Factory name of the video decoder that playbin plugged in, e.g. `avdec_h264`.
+    fn video_decoder(&self) -> Option<String> {
+        use gstreamer::prelude::GstBinExtManual;
+
+        let bin = self.pipeline.downcast_ref::<gstreamer::Bin>()?;
+
+        bin.iterate_recurse()
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter_map(|element| element.factory())
+            .find(|factory| {
+                factory_klass(factory)
+                    .is_some_and(|klass| klass.contains("Decoder") && klass.contains("Video"))
+            })
+            .map(|factory| factory.name().to_string())
+    }
+
+    /// Tags of the first video stream, as collected by playbin.
+    fn video_tags(&self) -> Option<gstreamer::TagList> {
+        self.pipeline
+            .emit_by_name("get-video-tags", &[&0i32])
+            .ok()
+            .flatten()
+            .and_then(|tags| tags.get::<Option<gstreamer::TagList>>().ok().flatten())
+    }
+
+    fn set_state_with_timeout(&mut self, state: gstreamer::State, timeout: Duration) -> Result<()> {
+        match self.pipeline.set_state(state).map_err(VidSeqError::from)? {
             gstreamer::StateChangeSuccess::Success => Ok(()),
             gstreamer::StateChangeSuccess::Async => self.wait_async_done(timeout),
-            gstreamer::StateChangeSuccess::NoPreroll => {
-                Err(anyhow::anyhow!("live sources not supported"))
-            }
+            gstreamer::StateChangeSuccess::NoPreroll => Err(VidSeqError::LiveSource),
         }
     }
 
-    fn wait_async_done(&self, timeout: Duration) -> anyhow::Result<()> {
+    /// Waits for the pipeline to finish its pending state change, seek or step.
+    ///
+    /// Reaching the end of the stream also counts as finishing, check `eos` to tell the two apart.
+    fn wait_async_done(&mut self, timeout: Duration) -> Result<()> {
+        let bus = self
+            .pipeline
+            .bus()
+            .ok_or_else(|| VidSeqError::Internal("pipeline has no bus".to_owned()))?;
+
         loop {
-            let msg = self
-                .pipeline
-                .bus()
-                .expect("bus exists on pipeline")
-                .timed_pop(Some(timeout.try_into()?));
+            let msg = bus.timed_pop(Some(clock_time(timeout)?));
 
             if let Some(msg) = msg {
                 match msg.view() {
                     MessageView::AsyncDone(_) => return Ok(()),
-                    MessageView::Error(err) => return Err(err.error().into()),
+                    MessageView::Eos(_) => {
+                        self.eos = true;
+
+                        return Ok(());
+                    }
+                    MessageView::Error(err) => return Err(VidSeqError::from(err.error())),
+                    MessageView::Toc(toc) => self.toc = Some(toc.toc().0),
                     _ => {}
                 }
             } else {
-                return Err(anyhow::anyhow!("Timed out waiting for ASYNC_DONE"));
+                return Err(VidSeqError::Timeout { timeout });
             }
         }
     }
 }
 
-impl Drop for VideoSequenceInner {
-    fn drop(&mut self) {
-        self.pipeline.set_state(gstreamer::State::Null).unwrap();
+/// Decodes the whole video at `uri` as fast as possible, counting its frames.
+///
+/// Fails with `VidSeqError::Timeout` when decoding stalls for `timeout`.
+fn count_frames(uri: &str, timeout: Duration) -> Result<u64> {
+    use gstreamer::MessageType;
+
+    check_or_init_gst()?;
+
+    let pipeline = ElementFactory::make("playbin", None)?;
+
+    pipeline.set_property("uri", uri)?;
+    pipeline.set_property("audio-sink", ElementFactory::make("fakesink", None)?)?;
+
+    // fakesink does not sync to the clock, so this runs as fast as frames can be decoded
+    let sink = ElementFactory::make("fakesink", None)?;
+
+    let count = Arc::new(AtomicU64::new(0));
+
+    if let Some(pad) = sink.static_pad("sink") {
+        let count = count.clone();
+
+        pad.add_probe(PadProbeType::BUFFER, move |_, _| {
+            count.fetch_add(1, Ordering::Relaxed);
+
+            PadProbeReturn::Ok
+        });
     }
-}
 
-/// The primary struct, encapsulates an opened video.
-///
-/// Keep in mind that, at least in this version, video-seeking is not exactly perfect;
-/// - it assumes a constant frame rate over the video, any divergence or "lag" can mess up the total assumed frames
-/// - it does this based on converted frame duration, together with above assumption, this may lead to skipped or duplicate frames
-/// - the assumed total amount of frames may "overshoot", and frames at the end of the video may not be "there"
-pub struct VideoSequence {
-    inner: VideoSequenceInner,
+    pipeline.set_property("video-sink", sink)?;
 
-    per_frame: Duration,
-    frames: u64,
-    current_index: u64,
-}
+    let _stop = StopOnDrop(pipeline.clone());
 
-impl VideoSequence {
-    /// Open a video file and initialize gstreamer objects.
-    ///
-    /// A bunch of things can go wrong;
-    /// - the wrong file was supplied
-    /// - the file was not a video file
-    /// - the right gstreamer plugins are not installed to
-    /// - gstreamer borks itself
-    pub fn open<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
-        let uri = format!(
-            "file://{}",
-            path.as_ref()
-                .canonicalize()?
-                .to_str()
-                .ok_or(anyhow::anyhow!("path cannot be a string"))?
+    pipeline.set_state(gstreamer::State::Playing)?;
+
+    let bus = pipeline
+        .bus()
+        .ok_or_else(|| VidSeqError::Internal("pipeline has no bus".to_owned()))?;
+
+    // decoding the whole video may take long, so only give up once no frame arrived for `timeout`
+    let mut counted = 0;
+
+    let msg = loop {
+        let msg = bus.timed_pop_filtered(
+            Some(clock_time(timeout)?),
+            &[MessageType::Eos, MessageType::Error],
         );
 
-        check_or_init_gst();
+        let now = count.load(Ordering::Relaxed);
 
-        let pipeline = ElementFactory::make("playbin", None)?;
+        if msg.is_some() || now == counted {
+            break msg;
+        }
 
-        pipeline.set_property("uri", uri)?;
-        pipeline.set_property(
-            "audio-sink",
-            ElementFactory::make("fakesink", Some("fakeaudio"))?,
-        )?;
+        counted = now;
+    };
 
-        let videocaps = gstreamer::Caps::builder("video/x-raw")
-            .field("format", "RGB")
-            .build();
+    match msg.as_ref().map(|msg| msg.view()) {
+        Some(MessageView::Error(err)) => Err(VidSeqError::from(err.error())),
+        Some(_) => Ok(count.load(Ordering::Relaxed)),
+        None => Err(VidSeqError::Timeout { timeout }),
+    }
+}
 
-        let appsink = ElementFactory::make("appsink", None)
-            .map_err(|_| anyhow::anyhow!("appsink is missing"))?
-            .dynamic_cast::<gstreamer_app::AppSink>()
-            .expect("Sink element is expected to be an appsink!");
+fn factory_klass(factory: &ElementFactory) -> Option<String> {
+    factory
+        .metadata(&gstreamer::ELEMENT_METADATA_KLASS)
+        .map(|klass| klass.to_string())
+}
 
-        appsink.set_property("caps", videocaps)?;
-        pipeline.set_property("video-sink", appsink.clone())?;
+/// Ranks the decoder factory `name` above every other video decoder, so that playbin picks it whenever it can handle the stream.
+///
+/// Like `derank_hardware_decoders`, this affects every pipeline in the process.
+fn prefer_decoder(name: &str) -> Result<()> {
+    use gstreamer::{
+        glib::translate::{FromGlib, IntoGlib},
+        prelude::PluginFeatureExtManual,
+    };
+
+    let factory = ElementFactory::find(name).ok_or_else(|| missing_element(name))?;
+
+    let decoders = ElementFactory::list_get_elements(
+        gstreamer::ElementFactoryListType::DECODER | gstreamer::ElementFactoryListType::MEDIA_VIDEO,
+        gstreamer::Rank::None,
+    );
+
+    let top = decoders
+        .iter()
+        .filter(|other| other.name() != factory.name())
+        .map(|other| other.rank().into_glib())
+        .max()
+        .unwrap_or(0);
+
+    if factory.rank().into_glib() <= top {
+        // Ranks are plain integers to GStreamer, so any value is a valid one.
+        factory.set_rank(unsafe { gstreamer::Rank::from_glib(top + 1) });
+    }
 
-        let inner = VideoSequenceInner { pipeline, appsink };
+    Ok(())
+}
 
-        inner.set_state_with_timeout(gstreamer::State::Paused, Duration::from_secs(10))?;
+/// Whether the decoder factory `name` is marked as hardware-accelerated.
+fn is_hardware_decoder(name: &str) -> bool {
+    ElementFactory::find(name)
+        .and_then(|factory| factory_klass(&factory))
+        .is_some_and(|klass| klass.contains("Hardware"))
+}
 
-        let sample = inner.appsink.pull_preroll()?;
+/// Gives every hardware video decoder the lowest rank, so that playbin never picks one on its own.
+///
+/// Element ranks live in the global gstreamer registry, so this affects every pipeline in the process.
+fn derank_hardware_decoders() {
+    use gstreamer::prelude::PluginFeatureExtManual;
+
+    let decoders = ElementFactory::list_get_elements(
+        gstreamer::ElementFactoryListType::DECODER | gstreamer::ElementFactoryListType::MEDIA_VIDEO,
+        gstreamer::Rank::None,
+    );
+
+    for factory in decoders {
+        if factory_klass(&factory).is_some_and(|klass| klass.contains("Hardware")) {
+            factory.set_rank(gstreamer::Rank::None);
+        }
+    }
+}
 
-        let caps = sample
-            .caps_owned()
-            .ok_or(anyhow::anyhow!("No data in video"))?;
+/// Wraps `appsink` in a bin that runs every frame through `videoconvert` first,
+/// so that frames are converted in system memory instead of being handed over in whatever memory the decoder used.
+fn converting_sink(appsink: &gstreamer_app::AppSink) -> Result<gstreamer::Element> {
+    use gstreamer::prelude::GstBinExtManual;
 
-        let struc = caps.structure(0).expect("caps has structure");
+    let bin = gstreamer::Bin::new(None);
 
-        let fraction: gstreamer::Fraction = struc
-            .get("framerate")
-            .map_err(|_| anyhow::anyhow!("Could not determine frame rate for seeking"))?;
+    let convert =
+        ElementFactory::make("videoconvert", None).map_err(|_| missing_element("videoconvert"))?;
 
-        let num = *fraction.0.numer();
+    bin.add_many(&[&convert, appsink.upcast_ref()])?;
+    convert.link(appsink)?;
 
-        let denom = *fraction.0.denom();
+    let pad = convert
+        .static_pad("sink")
+        .ok_or_else(|| VidSeqError::Internal("videoconvert has no sink pad".to_owned()))?;
 
-        let g_sec: Duration = gstreamer::ClockTime::SECOND.into();
+    bin.add_pad(&gstreamer::GhostPad::with_target(Some("sink"), &pad)?)?;
 
-        let per_frame: Duration = g_sec.mul_f32(denom as f32).div_f32(num as f32);
+    Ok(bin.upcast())
+}
 
-        let duration: gstreamer::ClockTime = inner
-            .pipeline
-            .query_duration()
-            .ok_or(anyhow::anyhow!("Could not determine duration of video"))?;
+/// Whether any memory of `buffer` is a DMABuf, which hardware decoders like to hand out and which is slow or impossible to map.
+fn is_dmabuf(buffer: &gstreamer::BufferRef) -> bool {
+    buffer
+        .iter_memories()
+        .any(|memory| memory.is_type("dmabuf"))
+}
 
-        let duration: Duration = duration.into();
+/// Caps that the appsink accepts, optionally pinned to a specific size.
+fn output_caps(format: PixelFormat, size: Option<(u32, u32)>) -> gstreamer::Caps {
+    let mut caps = gstreamer::Caps::builder("video/x-raw").field("format", format.as_str());
 
-        let frames = (duration.as_nanos() / per_frame.as_nanos()) as u64;
+    if let Some((width, height)) = size {
+        caps = caps
+            .field("width", width as i32)
+            .field("height", height as i32)
+            .field("pixel-aspect-ratio", gstreamer::Fraction::new(1, 1));
+    }
 
-        let mut s = Self {
-            inner,
-            per_frame,
-            frames,
-            current_index: 0,
-        };
+    caps.build()
+}
 
-        s.raw_seek(0)?;
+fn missing_element(factory: &str) -> VidSeqError {
+    VidSeqError::MissingElement {
+        factory: factory.to_owned(),
+    }
+}
 
-        return Ok(s);
+/// Fills in whichever of `width` and `height` is missing from the aspect ratio of `source`, `None` if neither is given.
+fn scaled_size(
+    source: (u32, u32),
+    (width, height): (Option<u32>, Option<u32>),
+) -> Result<Option<(u32, u32)>> {
+    if width == Some(0) || height == Some(0) {
+        return Err(VidSeqError::InvalidArgument(
+            "output size must not be zero".to_owned(),
+        ));
     }
 
-    fn raw_seek(&mut self, index: u64) -> anyhow::Result<()> {
-        use gstreamer::{ClockTime, SeekFlags, SeekType};
+    let scale = |length: u32, from: u32, to: u32| {
+        ((length as u64 * to as u64 + from as u64 / 2) / from as u64).max(1) as u32
+    };
 
-        if index > self.frames {
-            return Err(anyhow::anyhow!("frame range exceeds file duration"));
-        }
+    Ok(match (width, height) {
+        (Some(width), Some(height)) => Some((width, height)),
+        (Some(width), None) => Some((width, scale(source.1, source.0, width))),
+        (None, Some(height)) => Some((scale(source.0, source.1, height), height)),
+        (None, None) => None,
+    })
+}
 
-        let timestamp: ClockTime = self.per_frame.mul_f64(index as f64).try_into()?;
+/// Checks that `uri` starts with a scheme, like `https:`, as gstreamer would not know what to do with it otherwise.
+fn check_uri(uri: &str) -> Result<()> {
+    let scheme = uri.split_once(':').map(|(scheme, _)| scheme);
+
+    let valid = scheme.is_some_and(|scheme| {
+        scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    });
+
+    if !valid {
+        return Err(VidSeqError::InvalidArgument(format!(
+            "{:?} is not a URI, it has no scheme",
+            uri
+        )));
+    }
 
-        let flags = SeekFlags::ACCURATE | SeekFlags::FLUSH;
+    Ok(())
+}
 
-        self.inner
-            .pipeline
-            .seek(
-                1.0,
-                flags,
-                SeekType::Set,
-                timestamp,
-                SeekType::None,
-                ClockTime::ZERO,
-            )
-            .map_err(|e| anyhow::anyhow!("seek event not handled: {}", e))?;
+/// Turns a local path into a properly escaped `file://` URI.
+///
+/// With `canonicalize` unset, the path is only made absolute, leaving any symlinks in it unresolved.
+fn path_to_uri(path: &Path, canonicalize: bool) -> Result<String> {
+    let not_found = |e: std::io::Error| match e.kind() {
+        std::io::ErrorKind::NotFound => VidSeqError::NotFound {
+            path: path.to_owned(),
+        },
+        _ => e.into(),
+    };
+
+    let path = if canonicalize {
+        path.canonicalize().map_err(not_found)?
+    } else {
+        // make sure it exists, like canonicalize would
+        path.metadata().map_err(not_found)?;
+
+        std::path::absolute(path)?
+    };
+
+    Ok(gstreamer::glib::filename_to_uri(path, None)?.into())
+}
 
-        self.inner.wait_async_done(Duration::from_secs(10))?;
+/// Shuts a pipeline down when dropped, so one that only lives for a single pass is stopped however that pass ends.
+pub(crate) struct StopOnDrop(pub(crate) gstreamer::Element);
 
-        self.current_index = index;
+impl Drop for StopOnDrop {
+    fn drop(&mut self) {
+        let _ = self.0.set_state(gstreamer::State::Null);
+    }
+}
 
-        Ok(())
+impl Drop for VideoSequenceInner {
+    fn drop(&mut self) {
+        // there is nobody left to report a failure to
+        let _ = self.pipeline.set_state(gstreamer::State::Null);
     }
+}
 
-    fn step(&mut self, count: u64) -> anyhow::Result<()> {
-        if count == 0 {
-            return Ok(());
+/// Options for opening a `VideoSequence`, start with `VideoSequence::builder`.
+#[derive(Debug, Clone)]
+pub struct VideoSequenceBuilder {
+    canonicalize: bool,
+    eos_timeout: Duration,
+    max_dimensions: Option<(u32, u32)>,
+    oversize_policy: OversizePolicy,
+    buffer_pool: Option<u32>,
+    force_software_decode: bool,
+    crop_to_clean_aperture: bool,
+    resolution_change_policy: ResolutionChangePolicy,
+    orientation_mode: OrientationMode,
+    adaptive_seek: bool,
+    len_policy: LenPolicy,
+    pixel_format: PixelFormat,
+    system_memory: bool,
+    on_caps_change: Option<CapsCallback>,
+    decoder: Option<String>,
+    playbin3: bool,
+    output_size: (Option<u32>, Option<u32>),
+    frame_cache: Option<usize>,
+    step_threshold: u64,
+    seek_mode: SeekMode,
+    #[cfg(feature = "gl")]
+    gl: Option<gl::GlOutput>,
+}
+
+impl Default for VideoSequenceBuilder {
+    fn default() -> Self {
+        Self {
+            canonicalize: true,
+            eos_timeout: DEFAULT_EOS_TIMEOUT,
+            max_dimensions: None,
+            oversize_policy: OversizePolicy::Reject,
+            buffer_pool: None,
+            force_software_decode: false,
+            crop_to_clean_aperture: true,
+            resolution_change_policy: ResolutionChangePolicy::Allow,
+            orientation_mode: OrientationMode::Report,
+            adaptive_seek: false,
+            len_policy: LenPolicy::Estimate,
+            pixel_format: PixelFormat::Rgb,
+            system_memory: false,
+            on_caps_change: None,
+            decoder: None,
+            playbin3: false,
+            output_size: (None, None),
+            frame_cache: None,
+            step_threshold: 1,
+            seek_mode: SeekMode::Accurate,
+            #[cfg(feature = "gl")]
+            gl: None,
         }
+    }
+}
 
-        use gstreamer::ClockTime;
+/// What to do with videos that exceed `VideoSequenceBuilder::max_decode_dimensions`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OversizePolicy {
+    /// Fail opening with `VidSeqError::ResolutionTooLarge`.
+    Reject,
+    /// Have gstreamer scale frames down to fit, keeping their aspect ratio.
+    ///
+    /// Scaling does not work with `VideoSequenceBuilder::gl_context`, so oversized videos are rejected with `VidSeqError::InvalidArgument` then.
+    Downscale,
+}
 
-        let step_dur: ClockTime = self.per_frame.mul_f64(count as f64).try_into()?;
+/// Where rotation and mirroring signalled by the video are taken care of, see `VideoSequenceBuilder::orientation_mode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrientationMode {
+    /// Frames are handed out as decoded, `VideoSequence::orientation` tells how to turn them upright.
+    Report,
+    /// gstreamer turns every frame upright before handing it out, which costs a pass over every frame.
+    Apply,
+    /// Like `Report`, but JPEGs written by `VideoSequence::save_frame` carry the orientation as EXIF metadata,
+    /// so that viewers which honor it show them upright without the pixels being touched.
+    Exif,
+}
 
-        let ev = gstreamer::event::Step::new(step_dur, 1.0, true, false);
+/// How precisely seeks land, see `VideoSequenceBuilder::seek_mode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeekMode {
+    /// Decode up to the exact frame that was asked for.
+    Accurate,
+    /// Land on the keyframe nearest to the frame that was asked for, skipping the decoding in between.
+    ///
+    /// This trades precision for throughput, e.g. for scanning through a long video; frames can be seconds off,
+    /// `VideoSequence::current_index` tells which frame was actually landed on.
+    Fast,
+}
 
-        if !self.inner.pipeline.send_event(ev) {
-            return Err(anyhow::anyhow!("Step event not handled"));
-        }
+/// How `VideoSequence::len` gets its number, see `VideoSequenceBuilder::len_policy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LenPolicy {
+    /// Use the container's frame count if it has one, or estimate it from the duration and frame rate.
+    Estimate,
+    /// Count every frame on the first call to `len`, and keep returning that count.
+    ExactLazy,
+}
 
-        self.inner.wait_async_done(Duration::from_secs(10))?;
+/// Layout of the pixels that frames are decoded into, see `VideoSequenceBuilder::pixel_format`.
+///
+/// Parses from the gstreamer format names, e.g. `"GRAY8".parse::<PixelFormat>()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// 8-bit red, green and blue.
+    Rgb,
+    /// 8-bit red, green, blue and alpha.
+    Rgba,
+    /// 8-bit luma only.
+    Gray8,
+}
 
-        self.current_index = self.current_index + count;
+impl PixelFormat {
+    /// Bytes per pixel.
+    pub fn channels(self) -> usize {
+        match self {
+            Self::Rgb => 3,
+            Self::Rgba => 4,
+            Self::Gray8 => 1,
+        }
+    }
 
-        Ok(())
+    /// The name gstreamer uses for this format in caps.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Rgb => "RGB",
+            Self::Rgba => "RGBA",
+            Self::Gray8 => "GRAY8",
+        }
     }
 
-    fn seek(&mut self, index: u64) -> anyhow::Result<()> {
-        if index < self.current_index {
-            self.raw_seek(index)
-        } else if index > self.current_index {
-            let delta = index - self.current_index;
+    fn from_video_format(format: gstreamer_video::VideoFormat) -> Option<Self> {
+        match format {
+            gstreamer_video::VideoFormat::Rgb => Some(Self::Rgb),
+            gstreamer_video::VideoFormat::Rgba => Some(Self::Rgba),
+            gstreamer_video::VideoFormat::Gray8 => Some(Self::Gray8),
+            _ => None,
+        }
+    }
+}
 
-            const MAX_DELTA: u64 = 1;
+impl std::str::FromStr for PixelFormat {
+    type Err = VidSeqError;
 
-            if delta > MAX_DELTA {
-                self.raw_seek(index)
-            } else {
-                self.step(delta)
-            }
-        } else {
-            Ok(())
-        }
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        [Self::Rgb, Self::Rgba, Self::Gray8]
+            .into_iter()
+            .find(|format| format.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| VidSeqError::UnsupportedFormat {
+                format: s.to_owned(),
+            })
     }
+}
 
-    /// Does its best to grab the frame at a frame index, see struct documentation for caveats.
-    ///
-    /// Can return a "Failed to pull preroll sample" error to note that frame at current index is not available.
-    pub fn get_frame(&mut self, index: u64) -> anyhow::Result<Option<RgbImage>> {
-        self.seek(index)?;
+/// What to do when a stream switches resolution partway through, see `VideoSequenceBuilder::resolution_change_policy`.
+#[derive(Clone)]
+pub enum ResolutionChangePolicy {
+    /// Hand out frames at whatever size they were decoded at.
+    Allow,
+    /// Fail getting frames that differ in size from the previous ones with `VidSeqError::ResolutionChanged`.
+    Reject,
+    /// Call the function with the previous and the new dimensions, then hand out the frame as usual.
+    Notify(ResolutionCallback),
+}
 
-        let sample = self.inner.appsink.pull_preroll()?;
+/// Called with the previous and the new `(width, height)` of the frames.
+pub type ResolutionCallback = Arc<dyn Fn((u32, u32), (u32, u32)) + Send + Sync>;
 
-        if sample.buffer().is_none() {
-            return Ok(None);
-        }
+type CapsFn = dyn FnMut(&gstreamer::Caps) + Send;
 
-        convert_sample_to_image(sample).map(|i| Some(i))
+/// Shared so that cloned builders, and sequences opened from them, all call the same function.
+#[derive(Clone)]
+struct CapsCallback(Arc<Mutex<CapsFn>>);
+
+impl std::fmt::Debug for CapsCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("CapsCallback(..)")
     }
+}
 
-    /// Assumed amount of frames in this sequence, see struct documentation for caveats.
-    pub fn len(&self) -> u64 {
-        self.frames
+impl std::fmt::Debug for ResolutionChangePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Allow => f.write_str("Allow"),
+            Self::Reject => f.write_str("Reject"),
+            Self::Notify(_) => f.write_str("Notify(..)"),
+        }
     }
 }
 
-/// Converts a single RGB frame sample to an `image::RgbImage`
-pub fn convert_sample_to_image(sample: gstreamer::Sample) -> anyhow::Result<RgbImage> {
-    let caps = sample
-        .caps()
-        .ok_or(anyhow::anyhow!("could not grab caps"))?;
-    let buffer = sample
-        .buffer()
-        .ok_or(anyhow::anyhow!("could not grab buffer"))?;
+impl VideoSequenceBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether to resolve symlinks in the path before handing it to gstreamer, on by default.
+    ///
+    /// When turned off, relative paths are only made absolute against the current directory,
+    /// so that the symlink itself is opened instead of what it points to.
+    pub fn canonicalize(mut self, canonicalize: bool) -> Self {
+        self.canonicalize = canonicalize;
+        self
+    }
+
+    /// See `VideoSequence::set_eos_timeout`.
+    pub fn eos_timeout(mut self, timeout: Duration) -> Self {
+        self.eos_timeout = timeout;
+        self
+    }
 
-    let mut buf = vec![0u8; buffer.size()];
+    /// Limits the resolution of videos that will be decoded, see `oversize_policy` for what happens to larger ones.
+    ///
+    /// The check happens right after the first frame has been prerolled, before any further decoding takes place.
+    pub fn max_decode_dimensions(mut self, width: u32, height: u32) -> Self {
+        self.max_dimensions = Some((width, height));
+        self
+    }
 
-    buffer
-        .copy_to_slice(0, &mut buf)
-        .map_err(|_| anyhow::anyhow!("could not copy full image buffer"))?;
+    /// Has gstreamer scale frames to `width` by `height` before handing them out, e.g. for thumbnails.
+    ///
+    /// If only one of them is given, the other follows from the aspect ratio of the video; frames are left alone if they already have that size.
+    /// `max_decode_dimensions` applies to the scaled size. Scaling does not work with `gl_context`, opening fails then.
+    pub fn output_size(mut self, width: Option<u32>, height: Option<u32>) -> Self {
+        self.output_size = (width, height);
+        self
+    }
 
-    let struc = caps.structure(0).expect("caps has structure");
+    /// What to do with videos that exceed `max_decode_dimensions`, rejects them by default.
+    pub fn oversize_policy(mut self, policy: OversizePolicy) -> Self {
+        self.oversize_policy = policy;
+        self
+    }
 
-    let width: i32 = struc.get("width")?;
-    let height: i32 = struc.get("height")?;
-    let format: String = struc.get("format")?;
+    /// Has the decoder output into a pool of at most `max_buffers` recycled buffers, instead of allocating new ones.
+    ///
+    /// Buffers go back into the pool once the `FrameView` holding them is dropped;
+    /// holding on to `max_buffers` views at once stalls decoding until one is released.
+    pub fn buffer_pool(mut self, max_buffers: u32) -> Self {
+        self.buffer_pool = Some(max_buffers);
+        self
+    }
 
-    if format != "RGB" {
-        return Err(anyhow::anyhow!("Need RGB frame sample to convert to image"));
+    /// Keeps gstreamer from picking hardware decoders, so that decoded pixels are the same on every machine.
+    ///
+    /// This lowers the rank of every decoder that gstreamer marks as hardware-accelerated,
+    /// which affects every pipeline in the process, not just the ones opened by this builder.
+    /// `VideoSequence::decoder_name` tells which decoder ended up being used.
+    pub fn force_software_decode(mut self, force: bool) -> Self {
+        self.force_software_decode = force;
+        self
     }
 
-    RgbImage::from_raw(width as u32, height as u32, buf)
-        .ok_or(anyhow::anyhow!("image buffer was not sufficient"))
+    /// Has playbin use the video decoder made by the element factory `name`, e.g. `vaapih264dec` or `nvh264dec`.
+    ///
+    /// This ranks the decoder above all others, which affects every pipeline in the process like `force_software_decode` does.
+    /// Opening fails if the factory is missing, or if playbin ends up using another decoder anyway, e.g. because it does not support the stream.
+    pub fn decoder(mut self, name: &str) -> Self {
+        self.decoder = Some(name.to_owned());
+        self
+    }
+
+    /// Builds the pipeline around `playbin3` instead of `playbin`, which plugs in decoders with `decodebin3`; off by default.
+    pub fn playbin3(mut self, enable: bool) -> Self {
+        self.playbin3 = enable;
+        self
+    }
+
+    fn playbin_factory(&self) -> &'static str {
+        if self.playbin3 {
+            "playbin3"
+        } else {
+            "playbin"
+        }
+    }
+
+    /// Whether to crop frames to the clean aperture the stream signals, on by default.
+    ///
+    /// When turned off, frames include the padding or overscan around the aperture,
+    /// which is still reported by `VideoSequence::clean_aperture`.
+    pub fn crop_to_clean_aperture(mut self, crop: bool) -> Self {
+        self.crop_to_clean_aperture = crop;
+        self
+    }
+
+    /// What to do when frames stop matching the size the video had when it was opened, allows it by default.
+    ///
+    /// Frames are always sized after their own caps, `VideoSequence::dimensions` follows along with any allowed change.
+    pub fn resolution_change_policy(mut self, policy: ResolutionChangePolicy) -> Self {
+        self.resolution_change_policy = policy;
+        self
+    }
+
+    /// Whether frames are turned upright by gstreamer or only come with their orientation, only reports it by default.
+    ///
+    /// Reporting is cheaper when frames end up somewhere that can rotate them for free, like a GPU;
+    /// `Orientation::apply` turns a reported frame upright on demand.
+    pub fn orientation_mode(mut self, mode: OrientationMode) -> Self {
+        self.orientation_mode = mode;
+        self
+    }
+
+    /// Whether seeks land on the exact frame or on the nearest keyframe, exact by default.
+    pub fn seek_mode(mut self, mode: SeekMode) -> Self {
+        self.seek_mode = mode;
+        self
+    }
+
+    /// How many frames forward a jump can be for it to step there, instead of flush-seeking, 1 by default.
+    ///
+    /// Stepping decodes every frame in between, while a seek decodes from the previous keyframe;
+    /// for mostly-forward access with small gaps, a higher threshold avoids needless seeks.
+    /// With `adaptive_seek`, this only decides which jumps are short enough to never need measuring.
+    pub fn step_threshold(mut self, frames: u64) -> Self {
+        self.step_threshold = frames;
+        self
+    }
+
+    /// Picks between stepping and flush-seeking for forward jumps based on what each costs for this video, off by default.
+    ///
+    /// The costs are measured with `VideoSequence::measure_seek_costs` on the first jump of more than one frame,
+    /// which takes a few extra seeks; afterwards every jump takes whichever is expected to be quicker.
+    pub fn adaptive_seek(mut self, adaptive: bool) -> Self {
+        self.adaptive_seek = adaptive;
+        self
+    }
+
+    /// Keeps up to `capacity` frames returned by `VideoSequence::get_frame` around, so that getting them again skips seeking and decoding.
+    ///
+    /// The least recently used frame makes way once the cache is full. A cached frame is handed out without touching the pipeline,
+    /// so the sequence stays positioned where it was. Off by default.
+    pub fn frame_cache(mut self, capacity: usize) -> Self {
+        self.frame_cache = Some(capacity);
+        self
+    }
+
+    /// How `VideoSequence::len` gets its number, estimates it by default.
+    pub fn len_policy(mut self, policy: LenPolicy) -> Self {
+        self.len_policy = policy;
+        self
+    }
+
+    /// Which pixel format frames are decoded into, RGB by default.
+    ///
+    /// `get_frame` and friends convert other formats to RGB, `get_frame_dyn` hands frames out as they are.
+    pub fn pixel_format(mut self, format: PixelFormat) -> Self {
+        self.pixel_format = format;
+        self
+    }
+
+    /// Makes sure frames are handed out in system memory, off by default.
+    ///
+    /// This converts frames with `videoconvert` before they reach the appsink,
+    /// and copies frames that still arrive as DMABufs (as some hardware decoders output) into system memory before mapping them.
+    /// Without it, such frames are mapped as they are, which is slow and fails on some drivers.
+    /// Has no effect together with `gl_context`.
+    pub fn system_memory(mut self, system_memory: bool) -> Self {
+        self.system_memory = system_memory;
+        self
+    }
+
+    /// Calls `callback` whenever the caps that reach the appsink change, including when they are first negotiated while opening.
+    ///
+    /// This is the way to find out about a change in resolution or format as it happens, e.g. to resize buffers ahead of the next frame.
+    /// It is called from a gstreamer streaming thread, so it should return quickly.
+    pub fn on_caps_change<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&gstreamer::Caps) + Send + 'static,
+    {
+        self.on_caps_change = Some(CapsCallback(Arc::new(Mutex::new(callback))));
+        self
+    }
+
+    /// Has frames uploaded into GL textures of `context`, to be fetched with `VideoSequence::get_gl_frame`.
+    ///
+    /// Frames no longer reach the CPU, so the other `get_frame` variants fail on such a sequence,
+    /// and neither can frames be scaled with `output_size`, `OversizePolicy::Downscale` or `VideoSequence::thumbnail_track`.
+    /// `display` and `context` must stay alive, and `context` must not be destroyed, for as long as the sequence
+    /// or any `GlFrame` taken from it exists; textures are shared with it, so it has to be current on whichever thread uses them.
+    #[cfg(feature = "gl")]
+    pub fn gl_context(mut self, display: GLDisplay, context: GLContext) -> Self {
+        self.gl = Some(gl::GlOutput::new(display, context));
+        self
+    }
+
+    /// Describes the pipeline these options make, in the syntax of `gst-launch-1.0`, e.g. to check which elements get added.
+    ///
+    /// Nothing gets decoded; scaling to fit `max_decode_dimensions` only happens once a video is opened,
+    /// as it depends on its resolution, so the caps here are the ones the pipeline starts out with.
+    /// With only one side of `output_size` given, the caps leave the other one to follow from the aspect ratio.
+    pub fn describe(&self) -> Result<String> {
+        check_or_init_gst()?;
+
+        let mut caps = output_caps(self.pixel_format, None);
+
+        if let Some(structure) = caps.get_mut().and_then(|caps| caps.structure_mut(0)) {
+            let (width, height) = self.output_size;
+
+            if let Some(width) = width {
+                structure.set("width", width as i32);
+            }
+
+            if let Some(height) = height {
+                structure.set("height", height as i32);
+            }
+
+            if width.is_some() || height.is_some() {
+                structure.set("pixel-aspect-ratio", gstreamer::Fraction::new(1, 1));
+            }
+        }
+
+        let sink = format!("appsink caps=\"{}\"", caps);
+
+        let sink = if self.system_memory {
+            format!("videoconvert ! {}", sink)
+        } else {
+            sink
+        };
+
+        #[cfg(feature = "gl")]
+        let sink = match self.gl {
+            Some(_) => format!(
+                "glupload ! glcolorconvert ! appsink caps=\"{}\"",
+                gl::GlOutput::caps()
+            ),
+            None => sink,
+        };
+
+        let mut description = String::from(self.playbin_factory());
+
+        if self.orientation_mode == OrientationMode::Apply {
+            description.push_str(" video-filter=\"videoflip video-direction=auto\"");
+        }
+
+        description.push_str(&format!(
+            " video-sink=\"{}\" audio-sink=fakesink",
+            sink.replace('"', "\\\"")
+        ));
+
+        Ok(description)
+    }
+
+    /// Open a video file with these options, see `VideoSequence::open`.
+    pub fn open<P: AsRef<Path>>(&self, path: P) -> Result<VideoSequence> {
+        VideoSequence::open_with(path.as_ref(), self.clone())
+    }
+
+    /// Open a video by URI with these options, see `VideoSequence::open_uri`.
+    pub fn open_uri<S: AsRef<str>>(&self, uri: S) -> Result<VideoSequence> {
+        let uri = uri.as_ref();
+
+        check_uri(uri)?;
+
+        VideoSequence::open_uri_with(uri.to_owned(), self.clone())
+    }
+}
+
+/// How the amount of frames in a `VideoSequence` was determined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameCountSource {
+    /// Reported by the container, e.g. from an MP4 sample table.
+    Container,
+    /// Computed from the duration and frame rate, which can be off for videos that do not keep a constant frame rate.
+    Estimated,
+}
+
+/// How `VideoSequence::seek_buffer` got to its frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeekMethod {
+    /// Seeked straight to the buffer number, which is frame-exact.
+    Buffer,
+    /// Seeked to the timestamp that the frame rate puts the frame at, see struct documentation of `VideoSequence` for caveats.
+    Time,
+}
+
+/// The primary struct, encapsulates an opened video.
+///
+/// Keep in mind that, at least in this version, video-seeking is not exactly perfect;
+/// - it assumes a constant frame rate over the video, any divergence or "lag" can mess up the total assumed frames
+/// - it does this based on converted frame duration, together with above assumption, this may lead to skipped or duplicate frames
+/// - the assumed total amount of frames may "overshoot", and frames at the end of the video may not be "there"
+///
+/// Steps that land more than a frame duration away from where the frame rate puts the frame are redone as seeks,
+/// so that stepping never drifts further off than seeking directly would.
+///
+/// A sequence can be moved to another thread, so every thread can own one; it cannot be shared between threads,
+/// use `SharedVideoSequence` for that.
+pub struct VideoSequence {
+    inner: VideoSequenceInner,
+    uri: String,
+
+    decoder: Option<String>,
+    dimensions: (u32, u32),
+    clean_aperture: Option<Rect>,
+    format: gstreamer_video::VideoFormat,
+    orientation: Option<Orientation>,
+    framerate: (i32, i32),
+    duration: Duration,
+    per_frame: Duration,
+    frames: u64,
+    exact_frames: Cell<Option<u64>>,
+    count_failed: Cell<bool>,
+    frame_count_source: FrameCountSource,
+    current_index: u64,
+    adaptive_max_delta: Option<u64>,
+    last_pts: Option<Duration>,
+    info: VideoInfo,
+    cache: Option<cache::FrameCache>,
+    step_failed: bool,
+
+    options: VideoSequenceBuilder,
+}
+
+// gstreamer objects are reference counted thread-safely, so owning them on another thread is fine;
+// keeps a non-`Send` field from slipping in unnoticed
+const _: fn() = || {
+    fn assert_send<T: Send>() {}
+
+    assert_send::<VideoSequence>();
+};
+
+impl VideoSequence {
+    /// Open a video file and initialize gstreamer objects.
+    ///
+    /// A bunch of things can go wrong;
+    /// - the wrong file was supplied
+    /// - the file was not a video file
+    /// - the right gstreamer plugins are not installed to
+    /// - gstreamer borks itself
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        VideoSequenceBuilder::new().open(path)
+    }
+
+    /// Open a video from any URI gstreamer can read, e.g. `https://` or `rtsp://`, instead of a local path.
+    ///
+    /// The URI is handed to gstreamer as is, so it has to be properly escaped already.
+    /// Streams that cannot preroll, like live ones, fail with `VidSeqError::LiveSource`;
+    /// seeking in remote files only works if the server supports it, e.g. HTTP range requests.
+    pub fn open_uri<S: AsRef<str>>(uri: S) -> Result<Self> {
+        VideoSequenceBuilder::new().open_uri(uri)
+    }
+
+    /// Opens a video just to grab its first frame, e.g. for a poster image.
+    ///
+    /// Opening already prerolls the first frame, so no further decoding or seeking takes place.
+    pub fn first_frame<P: AsRef<Path>>(path: P) -> Result<RgbImage> {
+        Self::open(path)?
+            .pull_current()?
+            .ok_or(VidSeqError::OutOfRange {
+                index: 0,
+                frames: 0,
+            })?
+            .to_image()
+    }
+
+    /// Start configuring how to open a video, see `VideoSequenceBuilder`.
+    pub fn builder() -> VideoSequenceBuilder {
+        VideoSequenceBuilder::new()
+    }
+
+    /// Switches over to another video file, opened with the same options as this one.
+    ///
+    /// All metadata is updated to the new video. If opening fails, this keeps pointing at the current video.
+    pub fn reopen<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        *self = Self::open_with(path.as_ref(), self.options.clone())?;
+
+        Ok(())
+    }
+
+    /// Opens the same video again with the same options, as an independent sequence positioned at the first frame.
+    pub fn try_clone(&self) -> Result<Self> {
+        Self::open_uri_with(self.uri.clone(), self.options.clone())
+    }
+
+    fn open_with(path: &Path, options: VideoSequenceBuilder) -> Result<Self> {
+        Self::open_uri_with(path_to_uri(path, options.canonicalize)?, options)
+    }
+
+    fn open_uri_with(uri: String, options: VideoSequenceBuilder) -> Result<Self> {
+        let mut inner = VideoSequenceInner::new(&uri, &options)?;
+
+        inner.set_state_with_timeout(gstreamer::State::Paused, DEFAULT_TIMEOUT)?;
+
+        // playbin3 has no such property, its appsink just never gets a frame then
+        let n_video = inner
+            .pipeline
+            .property("n-video")
+            .ok()
+            .and_then(|n| n.get::<i32>().ok());
+
+        if n_video == Some(0) {
+            return Err(VidSeqError::NoVideoStream);
+        }
+
+        let sample = inner.appsink.pull_preroll()?;
+
+        let caps = sample.caps_owned().ok_or(VidSeqError::NoVideoStream)?;
+
+        let info = gstreamer_video::VideoInfo::from_caps(&caps)?;
+
+        let clean_aperture = sample.buffer().and_then(clean_aperture);
+
+        let mut dimensions = match clean_aperture {
+            Some(rect) if options.crop_to_clean_aperture => (rect.width, rect.height),
+            _ => (info.width(), info.height()),
+        };
+
+        if let Some(size) = scaled_size(dimensions, options.output_size)? {
+            if size != dimensions {
+                dimensions = size;
+
+                // picked up by the initial seek below
+                inner.scale_output(Some(dimensions))?;
+            }
+        }
+
+        if let Some((max_width, max_height)) = options.max_dimensions {
+            let (width, height) = dimensions;
+
+            if width > max_width || height > max_height {
+                match options.oversize_policy {
+                    OversizePolicy::Reject => {
+                        return Err(VidSeqError::ResolutionTooLarge {
+                            width,
+                            height,
+                            max_width,
+                            max_height,
+                        })
+                    }
+                    OversizePolicy::Downscale => {
+                        let scale = (max_width as f64 / width as f64)
+                            .min(max_height as f64 / height as f64);
+
+                        dimensions = (
+                            ((width as f64 * scale) as u32).max(1),
+                            ((height as f64 * scale) as u32).max(1),
+                        );
+
+                        // picked up by the initial seek below
+                        inner.scale_output(Some(dimensions))?;
+                    }
+                }
+            }
+        }
+
+        let mut fraction = info.fps();
+
+        if *fraction.0.numer() == 0 {
+            // variable frame rate, as is common in MKV and WebM; the highest rate at least bounds the frame positions
+            if let Some(max) = caps
+                .structure(0)
+                .and_then(|s| s.get::<gstreamer::Fraction>("max-framerate").ok())
+            {
+                fraction = max;
+            }
+        }
+
+        let num = *fraction.0.numer();
+
+        let denom = *fraction.0.denom();
+
+        if num < 0 || denom <= 0 {
+            return Err(VidSeqError::UnsupportedFrameRate { num, denom });
+        }
+
+        let duration: gstreamer::ClockTime = inner
+            .pipeline
+            .query_duration()
+            .ok_or(VidSeqError::UnknownDuration)?;
+
+        let duration: Duration = duration.into();
+
+        // containers with a sample table, like MP4, know exactly how many frames there are
+        let counted = inner
+            .pipeline
+            .query_duration::<gstreamer::format::Default>()
+            .map(|frames| frames.0)
+            .filter(|&frames| frames > 0);
+
+        let per_frame: Duration = if num == 0 {
+            match counted {
+                // without any rate to go by, spread the frames the container knows of evenly over the video
+                Some(frames) => duration / frames.min(u32::MAX as u64) as u32,
+                // nothing to go by at all, as with still images; treat it as a single frame that lasts the whole video
+                None => duration,
+            }
+            .max(Duration::from_nanos(1))
+        } else {
+            Duration::from_nanos(*gstreamer::ClockTime::SECOND * denom as u64 / num as u64)
+        };
+
+        if per_frame.is_zero() {
+            return Err(VidSeqError::UnsupportedFrameRate { num, denom });
+        }
+
+        let (frames, frame_count_source) = match counted {
+            Some(frames) => (frames, FrameCountSource::Container),
+            // a video that is shorter than one frame duration still has that one frame
+            None => (
+                (frames_in(duration, num, denom, per_frame) as u64).max(1),
+                FrameCountSource::Estimated,
+            ),
+        };
+
+        // frames that gstreamer already turned upright have nothing left to report
+        let orientation = match options.orientation_mode {
+            OrientationMode::Report | OrientationMode::Exif => {
+                inner.video_tags().and_then(|tags| {
+                    tags.get::<gstreamer::tags::ImageOrientation>()
+                        .and_then(|o| Orientation::from_tag(o.get()))
+                })
+            }
+            OrientationMode::Apply => None,
+        };
+
+        let decoder = inner.video_decoder();
+
+        if let Some(preferred) = &options.decoder {
+            if decoder.as_ref() != Some(preferred) {
+                return Err(VidSeqError::DecoderNotUsed {
+                    expected: preferred.clone(),
+                    actual: decoder,
+                });
+            }
+        }
+
+        let video_info = VideoInfo {
+            width: dimensions.0,
+            height: dimensions.1,
+            framerate: (num, denom),
+            duration,
+            per_frame,
+            hardware_decoder: decoder.as_deref().is_some_and(is_hardware_decoder),
+        };
+
+        let mut s = Self {
+            inner,
+            uri,
+            decoder,
+            dimensions,
+            clean_aperture,
+            format: info.format(),
+            orientation,
+            framerate: (num, denom),
+            duration,
+            per_frame,
+            frames,
+            exact_frames: Cell::new(None),
+            count_failed: Cell::new(false),
+            frame_count_source,
+            current_index: 0,
+            adaptive_max_delta: None,
+            last_pts: None,
+            info: video_info,
+            cache: options.frame_cache.map(cache::FrameCache::new),
+            step_failed: false,
+            options,
+        };
+
+        s.raw_seek(0)?;
+
+        return Ok(s);
+    }
+
+    fn raw_seek(&mut self, index: u64) -> Result<()> {
+        if index > self.frame_count() {
+            return Err(VidSeqError::OutOfRange {
+                index,
+                frames: self.frame_count(),
+            });
+        }
+
+        let timestamp = self.index_to_timestamp(index);
+
+        self.seek_to_time_by_mode(timestamp, index)
+    }
+
+    /// Seeks to the `n`-th buffer of the video, counting from zero, and positions the sequence at that index.
+    ///
+    /// Where the pipeline supports seeking in frames, this lands on that exact frame without going through timestamps,
+    /// so it does not rely on a constant frame rate; otherwise, it falls back to seeking by time like `get_frame` does.
+    /// Returns which of the two was used.
+    pub fn seek_buffer(&mut self, n: u64) -> Result<SeekMethod> {
+        if n > self.frame_count() {
+            return Err(VidSeqError::OutOfRange {
+                index: n,
+                frames: self.frame_count(),
+            });
+        }
+
+        if self.seekable_in_frames() && self.seek_to_buffer(n).is_ok() {
+            self.current_index = n;
+
+            return Ok(SeekMethod::Buffer);
+        }
+
+        self.raw_seek(n)?;
+
+        Ok(SeekMethod::Time)
+    }
+
+    /// Whether the pipeline reports that it can seek in frames, i.e. the `Default` format.
+    fn seekable_in_frames(&self) -> bool {
+        let mut query = gstreamer::query::Seeking::new(gstreamer::Format::Default);
+
+        self.inner.pipeline.query(&mut query) && query.result().0
+    }
+
+    /// Flush-seeks to the `n`-th buffer, without touching `current_index`.
+    fn seek_to_buffer(&mut self, n: u64) -> Result<()> {
+        use gstreamer::{format, SeekFlags, SeekType};
+
+        self.inner
+            .pipeline
+            .seek(
+                1.0,
+                SeekFlags::FLUSH | SeekFlags::ACCURATE,
+                SeekType::Set,
+                format::Default(n),
+                SeekType::None,
+                format::Default(0),
+            )
+            .map_err(|_| VidSeqError::SeekNotHandled)?;
+
+        self.inner.eos = false;
+
+        self.inner.wait_async_done(DEFAULT_TIMEOUT)
+    }
+
+    /// Flush-seeks so that the frame on screen at `timestamp` gets prerolled, without touching `current_index`.
+    fn seek_to_time(&mut self, timestamp: Duration) -> Result<()> {
+        self.seek_to_time_with(timestamp, gstreamer::SeekFlags::ACCURATE)
+    }
+
+    /// Flush-seeks to `timestamp` the way `SeekMode` says, and positions the sequence at frame `index`, the one on screen then.
+    ///
+    /// With `SeekMode::Fast`, the sequence is positioned at the keyframe nearest to `timestamp` instead, going by its timestamp.
+    fn seek_to_time_by_mode(&mut self, timestamp: Duration, index: u64) -> Result<()> {
+        use gstreamer::SeekFlags;
+
+        match self.options.seek_mode {
+            SeekMode::Accurate => self.seek_to_time(timestamp)?,
+            SeekMode::Fast => {
+                self.seek_to_time_with(timestamp, SeekFlags::KEY_UNIT | SeekFlags::SNAP_NEAREST)?
+            }
+        }
+
+        self.current_index = index;
+
+        if self.inner.eos {
+            self.mark_end(index);
+        } else if self.options.seek_mode == SeekMode::Fast {
+            // the keyframe that was snapped to is rarely the frame that was asked for
+            if let Some(pts) = self.preroll_pts()? {
+                self.current_index = self.timestamp_to_index(pts);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Flush-seeks to `timestamp` with extra `flags`, e.g. to land on a keyframe instead.
+    fn seek_to_time_with(
+        &mut self,
+        timestamp: Duration,
+        flags: gstreamer::SeekFlags,
+    ) -> Result<()> {
+        use gstreamer::{ClockTime, SeekFlags, SeekType};
+
+        let timestamp = clock_time(timestamp)?;
+
+        let flags = flags | SeekFlags::FLUSH;
+
+        self.inner
+            .pipeline
+            .seek(
+                1.0,
+                flags,
+                SeekType::Set,
+                timestamp,
+                SeekType::None,
+                ClockTime::ZERO,
+            )
+            .map_err(|_| VidSeqError::SeekNotHandled)?;
+
+        self.inner.eos = false;
+
+        self.inner.wait_async_done(DEFAULT_TIMEOUT)
+    }
+
+    fn step(&mut self, count: u64) -> Result<()> {
+        if count == 0 {
+            return Ok(());
+        }
+
+        if self.inner.eos {
+            // nothing left to step into, the sink will stay silent
+            self.current_index += count;
+
+            return Ok(());
+        }
+
+        let step_dur = clock_time(
+            self.index_to_timestamp(self.current_index + count)
+                - self.index_to_timestamp(self.current_index),
+        )?;
+
+        let ev = gstreamer::event::Step::new(step_dur, 1.0, true, false);
+
+        if !self.inner.pipeline.send_event(ev) {
+            return Err(VidSeqError::StepNotHandled);
+        }
+
+        let timeout = if self.in_tail(self.current_index + count) {
+            self.options.eos_timeout
+        } else {
+            DEFAULT_TIMEOUT
+        };
+
+        self.inner.wait_async_done(timeout)?;
+
+        if self.inner.eos {
+            self.mark_end(self.current_index + count);
+        }
+
+        self.current_index += count;
+
+        self.verify_step()
+    }
+
+    /// Whether `index` lies within the last second of the sequence, where the assumed frame count may overshoot.
+    fn in_tail(&self, index: u64) -> bool {
+        let tail = (Duration::from_secs(1).as_nanos() / self.per_frame.as_nanos()) as u64;
+
+        index.saturating_add(tail) >= self.frame_count()
+    }
+
+    /// Sets how long a step near the end of the video waits for its frame, before giving up.
+    ///
+    /// The end of the stream itself is detected immediately, this only bounds the wait when gstreamer stays silent.
+    pub fn set_eos_timeout(&mut self, timeout: Duration) {
+        self.options.eos_timeout = timeout;
+    }
+
+    /// Switches between frame-accurate and keyframe seeking, see `VideoSequenceBuilder::seek_mode`.
+    ///
+    /// Changing the mode empties the frame cache, if there is one.
+    pub fn set_seek_mode(&mut self, mode: SeekMode) {
+        if mode != self.options.seek_mode {
+            if let Some(cache) = &mut self.cache {
+                cache.clear();
+            }
+        }
+
+        self.options.seek_mode = mode;
+    }
+
+    /// Sets how many frames forward a jump can be for it to step there instead of flush-seeking, see `VideoSequenceBuilder::step_threshold`.
+    pub fn set_step_threshold(&mut self, frames: u64) {
+        self.options.step_threshold = frames;
+    }
+
+    /// Times both seeking strategies at every index, by flush-seeking to it and then stepping one frame forward.
+    ///
+    /// Comparing the two averages tells how many frames can be stepped through in the time of one flushing seek.
+    /// Indices outside of the sequence are skipped, the sequence is left positioned after the last sample.
+    pub fn measure_seek_costs(&mut self, sample_indices: &[u64]) -> Result<SeekStats> {
+        let mut flush_seeks = Vec::new();
+        let mut steps = Vec::new();
+
+        for &index in sample_indices {
+            if index >= self.frame_count() {
+                continue;
+            }
+
+            let start = Instant::now();
+
+            self.raw_seek(index)?;
+
+            flush_seeks.push(start.elapsed());
+
+            if index + 1 >= self.frame_count() || self.inner.eos {
+                continue;
+            }
+
+            let start = Instant::now();
+
+            self.step(1)?;
+
+            steps.push(start.elapsed());
+        }
+
+        Ok(SeekStats {
+            avg_step: average(&steps),
+            avg_flush_seek: average(&flush_seeks),
+        })
+    }
+
+    fn seek(&mut self, index: u64) -> Result<()> {
+        if index < self.current_index {
+            self.raw_seek(index)
+        } else if index > self.current_index {
+            let delta = index - self.current_index;
+
+            let threshold = self.options.step_threshold;
+
+            let max_delta = if self.options.adaptive_seek && delta > threshold {
+                self.adaptive_max_delta()?
+            } else {
+                threshold
+            };
+
+            if delta > max_delta || self.step_failed {
+                return self.raw_seek(index);
+            }
+
+            if let Err(e) = self.step(delta) {
+                // whatever made the step fail is likely to do so again, so this sticks to seeking from now on
+                gstreamer::gst_warning!(CAT, "stepping failed, seeking instead: {}", e);
+
+                self.step_failed = true;
+
+                return self.raw_seek(index);
+            }
+
+            Ok(())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// How many frames can be stepped through in the time of one flushing seek, measured on first use and cached.
+    fn adaptive_max_delta(&mut self) -> Result<u64> {
+        // stepping further than this would hardly ever pay off, and keeps a bad measurement from going overboard
+        const MAX_ADAPTIVE_DELTA: u64 = 120;
+
+        if let Some(max_delta) = self.adaptive_max_delta {
+            return Ok(max_delta);
+        }
+
+        let position = self.current_index;
+
+        let samples = [
+            self.frame_count() / 4,
+            self.frame_count() / 2,
+            self.frame_count() * 3 / 4,
+        ];
+
+        let stats = self.measure_seek_costs(&samples)?;
+
+        let max_delta = if stats.avg_step.is_zero() {
+            1
+        } else {
+            (stats.avg_flush_seek.as_nanos() / stats.avg_step.as_nanos()) as u64
+        }
+        .clamp(1, MAX_ADAPTIVE_DELTA);
+
+        self.adaptive_max_delta = Some(max_delta);
+
+        // measuring moved the position around, go back to where the caller expects it to be
+        self.raw_seek(position)?;
+
+        Ok(max_delta)
+    }
+
+    /// Does its best to grab the frame at a frame index, see struct documentation for caveats.
+    ///
+    /// Returns `None` when the video ended before reaching the index; an estimated frame count is brought down to match then,
+    /// so that `len` reflects where the frames actually end from that point on.
+    pub fn get_frame(&mut self, index: u64) -> Result<Option<RgbImage>> {
+        if let Some(frame) = self.cache.as_mut().and_then(|cache| cache.get(index)) {
+            return Ok(Some(frame.clone()));
+        }
+
+        let frame = self
+            .pull_frame(index)?
+            .map(|frame| frame.to_image())
+            .transpose()?;
+
+        // with `SeekMode::Fast`, the frame that got decoded may not be the one asked for
+        if let (Some(cache), Some(frame)) = (&mut self.cache, &frame) {
+            cache.insert(self.current_index, frame.clone());
+        }
+
+        Ok(frame)
+    }
+
+    /// Like `get_frame`, but wraps the frame in an `Arc` so it can be shared between threads without copying it.
+    pub fn get_frame_arc(&mut self, index: u64) -> Result<Option<Arc<RgbImage>>> {
+        Ok(self.get_frame(index)?.map(Arc::new))
+    }
+
+    /// Like `get_frame`, but hands out the decoded buffer itself instead of copying it into an image.
+    ///
+    /// The view maps the buffer read-only and holds a reference to it, so reading it never copies,
+    /// and it stays valid across later seeks, though holding it keeps the buffer from being reused (see `FrameView`).
+    pub fn get_frame_view(&mut self, index: u64) -> Result<Option<FrameView>> {
+        self.pull_frame(index)
+    }
+
+    /// Like `get_frame`, but keeps the frame in the pixel format it was decoded into, see `VideoSequenceBuilder::pixel_format`.
+    pub fn get_frame_dyn(&mut self, index: u64) -> Result<Option<Frame>> {
+        self.pull_frame(index)?
+            .map(|frame| frame.to_frame())
+            .transpose()
+    }
+
+    /// Like `get_frame`, but hands out the sample that reached the appsink as it is, caps included, without converting anything.
+    ///
+    /// `convert_sample_to_image` turns it into an image, `get_frame_view` maps it for reading instead.
+    /// The sample holds a reference to its buffer, so its data stays valid across later seeks;
+    /// with `VideoSequenceBuilder::buffer_pool`, holding on to samples keeps their buffers from being reused, like it does for views.
+    pub fn get_sample(&mut self, index: u64) -> Result<Option<gstreamer::Sample>> {
+        self.seek(index)?;
+
+        self.preroll_sample()
+    }
+
+    /// Grabs the frame nearest to `timestamp`, see `timestamp_to_index`.
+    pub fn get_frame_at(&mut self, timestamp: Duration) -> Result<Option<RgbImage>> {
+        self.get_frame(self.timestamp_to_index(timestamp))
+    }
+
+    /// Like `get_frame`, but writes the frame into an existing image, e.g. one backed by an arena instead of a `Vec`.
+    ///
+    /// `buf` has to be as large as the frame; returns `false` if there is no frame there, leaving `buf` untouched.
+    pub fn get_frame_into_buffer<C>(
+        &mut self,
+        index: u64,
+        buf: &mut ImageBuffer<Rgb<u8>, C>,
+    ) -> Result<bool>
+    where
+        C: Deref<Target = [u8]> + DerefMut,
+    {
+        match self.pull_frame(index)? {
+            Some(frame) => {
+                frame.copy_into(buf)?;
+
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Seeks to `index` and maps its decoded frame, `None` if there is no frame there.
+    fn pull_frame(&mut self, index: u64) -> Result<Option<FrameView>> {
+        self.seek(index)?;
+
+        self.pull_current()
+    }
+
+    /// The currently prerolled sample, `None` if the stream ended before it.
+    ///
+    /// An empty preroll that is not explained by the end of the stream is retried once with a fresh seek,
+    /// as it can happen spuriously right after a flush.
+    fn preroll_sample(&mut self) -> Result<Option<gstreamer::Sample>> {
+        for retry in [true, false] {
+            if self.inner.eos {
+                self.mark_end(self.current_index);
+
+                return Ok(None);
+            }
+
+            let sample = match self.inner.appsink.pull_preroll() {
+                Ok(sample) => sample,
+                Err(_) if self.inner.appsink.is_eos() => {
+                    self.inner.eos = true;
+                    self.mark_end(self.current_index);
+
+                    return Ok(None);
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            if sample.buffer().is_some() {
+                return Ok(Some(sample));
+            }
+
+            if let Some(bus) = self.inner.pipeline.bus() {
+                while let Some(msg) = bus.timed_pop(Some(gstreamer::ClockTime::ZERO)) {
+                    match msg.view() {
+                        MessageView::Eos(_) => self.inner.eos = true,
+                        MessageView::Error(err) => {
+                            return Err(VidSeqError::from(err.error()));
+                        }
+                        MessageView::Toc(toc) => self.inner.toc = Some(toc.toc().0),
+                        _ => {}
+                    }
+                }
+            }
+
+            if self.inner.eos {
+                self.mark_end(self.current_index);
+
+                return Ok(None);
+            }
+
+            if retry {
+                self.raw_seek(self.current_index)?;
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Notes that the stream ended before frame `index`, bringing an overshooting frame count down to where the frames actually end.
+    fn mark_end(&mut self, index: u64) {
+        if self.exact_frames.get().is_none() {
+            self.frames = self.frames.min(index.max(1));
+        }
+    }
+
+    /// Maps the currently prerolled frame, `None` if there is none.
+    fn pull_current(&mut self) -> Result<Option<FrameView>> {
+        let sample = match self.preroll_sample()? {
+            Some(sample) => sample,
+            None => return Ok(None),
+        };
+
+        self.last_pts = stream_time(&sample);
+
+        let caps = sample.caps().ok_or(VidSeqError::NoCaps)?;
+
+        // guards against reading the buffer with the wrong amount of bytes per pixel after a renegotiation
+        let format = gstreamer_video::VideoInfo::from_caps(caps)?.format();
+
+        if format != self.format {
+            return Err(VidSeqError::FormatMismatch {
+                expected: self.format.to_str().to_owned(),
+                actual: format.to_str().to_owned(),
+            });
+        }
+
+        let frame = FrameView::from_sample(
+            &sample,
+            self.options.crop_to_clean_aperture,
+            self.options.system_memory,
+        )?;
+
+        let size = (frame.width(), frame.height());
+
+        if size != self.dimensions {
+            match &self.options.resolution_change_policy {
+                ResolutionChangePolicy::Allow => {}
+                ResolutionChangePolicy::Reject => {
+                    return Err(VidSeqError::ResolutionChanged {
+                        from: self.dimensions,
+                        to: size,
+                    })
+                }
+                ResolutionChangePolicy::Notify(callback) => callback(self.dimensions, size),
+            }
+
+            self.dimensions = size;
+            (self.info.width, self.info.height) = size;
+        }
+
+        Ok(Some(frame))
+    }
+
+    /// Seeks to `index` and reads the presentation timestamp of its frame, `None` if there is no frame there.
+    fn pull_pts(&mut self, index: u64) -> Result<Option<Duration>> {
+        self.seek(index)?;
+
+        self.preroll_pts()
+    }
+
+    /// Position of the currently prerolled frame in the stream, according to its own timestamp.
+    fn preroll_pts(&mut self) -> Result<Option<Duration>> {
+        if self.inner.eos {
+            self.mark_end(self.current_index);
+
+            return Ok(None);
+        }
+
+        // the end of the stream may not have been announced on the bus yet
+        if self.inner.appsink.is_eos() {
+            self.inner.eos = true;
+            self.mark_end(self.current_index);
+
+            return Ok(None);
+        }
+
+        let sample = self.inner.appsink.pull_preroll()?;
+
+        Ok(stream_time(&sample))
+    }
+
+    /// Timestamp of the last frame that was handed out, as read from its buffer rather than computed from its index.
+    ///
+    /// Comparing it with `index_to_timestamp(current_index())` tells how far the video strays from a constant frame rate.
+    pub fn current_pts(&self) -> Option<Duration> {
+        self.last_pts
+    }
+
+    /// Seeks to `current_index` if a step left the prerolled frame more than a frame away from where that index should be,
+    /// as stepping by duration goes astray on videos that do not keep a constant frame rate.
+    fn verify_step(&mut self) -> Result<()> {
+        if let Some(pts) = self.preroll_pts()? {
+            if pts.abs_diff(self.index_to_timestamp(self.current_index)) > self.per_frame {
+                gstreamer::gst_debug!(
+                    CAT,
+                    "step to frame {} landed at {:?}, seeking instead",
+                    self.current_index,
+                    pts
+                );
+
+                self.raw_seek(self.current_index)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Measures how far the actual frame positions are off from the ones that index-based seeking assumes.
+    ///
+    /// For every index, this seeks to its assumed timestamp and reports the distance to the timestamp of the frame that was actually found there.
+    /// Large values mean that the constant frame rate assumption does not hold for this video.
+    pub fn measure_drift(&mut self, sample_indices: &[u64]) -> Result<Vec<(u64, Duration)>> {
+        let mut drift = Vec::new();
+
+        for &index in sample_indices {
+            if index >= self.frame_count() {
+                continue;
+            }
+
+            self.raw_seek(index)?;
+
+            if let Some(pts) = self.preroll_pts()? {
+                let assumed = self.index_to_timestamp(index);
+
+                drift.push((index, pts.abs_diff(assumed)));
+            }
+        }
+
+        Ok(drift)
+    }
+
+    /// Checks whether the first few frames of the video follow the frame rate from its caps.
+    ///
+    /// This samples the timestamps of a handful of consecutive frames and compares their spacing,
+    /// if they are uniform the index-based seeking of this crate can be trusted.
+    pub fn is_constant_frame_rate(&mut self) -> Result<bool> {
+        const PROBE_FRAMES: u64 = 16;
+
+        let tolerance = self.per_frame / 10;
+
+        let mut previous: Option<Duration> = None;
+
+        for index in 0..PROBE_FRAMES.min(self.frame_count()) {
+            let pts = match self.pull_pts(index)? {
+                Some(pts) => pts,
+                None => break,
+            };
+
+            if let Some(previous) = previous {
+                let delta = pts.saturating_sub(previous);
+
+                if delta.abs_diff(self.per_frame) > tolerance {
+                    return Ok(false);
+                }
+            }
+
+            previous = Some(pts);
+        }
+
+        Ok(true)
+    }
+
+    /// Walks through the frames from the current one onwards, stepping forward instead of seeking to each one.
+    ///
+    /// Iteration stops at the end of the video, or after yielding an error;
+    /// the sequence stays positioned at the last frame that was reached, and can be used as usual afterwards.
+    pub fn frames(&mut self) -> FrameIter<'_> {
+        FrameIter {
+            seq: self,
+            started: false,
+            done: false,
+        }
+    }
+
+    /// Grabs frames at fixed time intervals, at `origin`, `origin + interval`, `origin + 2 * interval`, and so on.
+    ///
+    /// Every grid point gets its own flushing seek, so this is best used with intervals spanning several frames;
+    /// with `SeekMode::Fast`, each one gets the keyframe nearest to it. Iteration stops at the first grid point past the end of the video.
+    pub fn frames_on_grid(
+        &mut self,
+        origin: Duration,
+        interval: Duration,
+    ) -> Result<GridFrames<'_>> {
+        if interval.is_zero() {
+            return Err(VidSeqError::InvalidArgument(
+                "grid interval cannot be zero".to_owned(),
+            ));
+        }
+
+        Ok(GridFrames {
+            seq: self,
+            next: Some(origin),
+            interval,
+        })
+    }
+
+    /// Grabs the frames at `indices`, walking the video forward only once instead of seeking back and forth.
+    ///
+    /// Indices are visited in ascending order, stepping across short gaps and seeking across longer ones the same way `get_frame` does,
+    /// see `VideoSequenceBuilder::step_threshold`; every distinct index is decoded once. The results come back in the order of `indices`,
+    /// leaving out indices that the video ended before reaching.
+    pub fn get_frames(&mut self, indices: &[u64]) -> Result<Vec<(u64, RgbImage)>> {
+        let mut sorted = indices.to_vec();
+
+        sorted.sort_unstable();
+        sorted.dedup();
+
+        let mut frames = HashMap::with_capacity(sorted.len());
+
+        for index in sorted {
+            if index >= self.frame_count() {
+                break;
+            }
+
+            self.seek(index)?;
+
+            match self.pull_current()? {
+                Some(frame) => frames.insert(index, frame.to_image()?),
+                None => break,
+            };
+        }
+
+        Ok(indices
+            .iter()
+            .filter_map(|index| frames.get(index).map(|frame| (*index, frame.clone())))
+            .collect())
+    }
+
+    /// Grabs the frame on screen at each of `times`, e.g. the start of every subtitle cue.
+    ///
+    /// The times are sorted first, so the video is only ever walked forward, and the results come back in that order.
+    /// Times that map onto the same frame share a single decode; times past the end of the video get `None`.
+    pub fn get_frames_at_times(
+        &mut self,
+        times: &[Duration],
+    ) -> Result<Vec<(Duration, Option<RgbImage>)>> {
+        let mut times = times.to_vec();
+
+        times.sort_unstable();
+
+        let mut frames: Vec<(Duration, Option<RgbImage>)> = Vec::with_capacity(times.len());
+        let mut last: Option<u64> = None;
+
+        for time in times {
+            if time >= self.duration {
+                frames.push((time, None));
+
+                continue;
+            }
+
+            let index = self.displayed_index(time);
+
+            let frame = match (last, frames.last()) {
+                (Some(last), Some((_, frame))) if last == index => frame.clone(),
+                _ => self.get_frame(index)?,
+            };
+
+            last = Some(index);
+
+            frames.push((time, frame));
+        }
+
+        Ok(frames)
+    }
+
+    /// Grabs the frame on screen halfway through the video, a common pick for thumbnails.
+    ///
+    /// This seeks by time, so it does not depend on the frame count being accurate.
+    /// With `SeekMode::Fast`, it is the keyframe nearest to the middle instead.
+    pub fn middle_frame(&mut self) -> Result<Option<RgbImage>> {
+        self.pull_frame_at_time(self.duration / 2)?
+            .map(|frame| frame.to_image())
+            .transpose()
+    }
+
+    /// Grabs the frame that a viewer sees at `timestamp`, going by the timestamps of the frames rather than the frame rate.
+    ///
+    /// That is the frame whose `[pts, pts + duration)` contains `timestamp`; when it falls in a gap between two frames,
+    /// the one before the gap is returned, as it stays on screen until the next one shows up.
+    /// `None` if `timestamp` lies before the first frame or past the end of the video.
+    pub fn displayed_frame_at(&mut self, timestamp: Duration) -> Result<Option<RgbImage>> {
+        if timestamp >= self.duration {
+            return Ok(None);
+        }
+
+        // an accurate seek lands on the frame containing the timestamp, or on the first one after a gap
+        self.seek_to_time(timestamp)?;
+
+        self.current_index = self.displayed_index(timestamp);
+
+        let pts = self.preroll_pts()?;
+
+        if pts.is_some_and(|pts| pts <= timestamp) {
+            return self
+                .pull_current()?
+                .map(|frame| frame.to_image())
+                .transpose();
+        }
+
+        // back off until landing on a frame before the gap
+        let mut lookback = self.per_frame;
+
+        let mut pts = loop {
+            let target = timestamp.saturating_sub(lookback);
+
+            self.seek_to_time(target)?;
+
+            self.current_index = self.displayed_index(target);
+
+            match self.preroll_pts()? {
+                Some(pts) if pts <= timestamp => break pts,
+                _ if target.is_zero() => return Ok(None),
+                _ => lookback *= 2,
+            }
+        };
+
+        // then walk forward up to the last frame that starts at or before the timestamp
+        let mut frame = self
+            .pull_current()?
+            .map(|frame| frame.to_image())
+            .transpose()?;
+
+        loop {
+            self.step_buffer(pts)?;
+
+            match self.preroll_pts()? {
+                Some(next) if next <= timestamp => {
+                    pts = next;
+
+                    frame = self
+                        .pull_current()?
+                        .map(|frame| frame.to_image())
+                        .transpose()?;
+                }
+                Some(next) => {
+                    pts = next;
+
+                    break;
+                }
+                None => break,
+            }
+        }
+
+        self.current_index = self.displayed_index(pts);
+
+        Ok(frame)
+    }
+
+    /// Steps from the frame at `pts` to the very next one, regardless of its timestamp.
+    ///
+    /// The sequence is positioned one frame after `pts`, so that running into the end of the stream clamps the frame count there.
+    fn step_buffer(&mut self, pts: Duration) -> Result<()> {
+        if self.inner.eos {
+            return Ok(());
+        }
+
+        self.current_index = self.displayed_index(pts) + 1;
+
+        let ev = gstreamer::event::Step::new(gstreamer::format::Buffers(1), 1.0, true, false);
+
+        if !self.inner.pipeline.send_event(ev) {
+            return Err(VidSeqError::StepNotHandled);
+        }
+
+        let timeout = if self.in_tail(self.displayed_index(pts) + 1) {
+            self.options.eos_timeout
+        } else {
+            DEFAULT_TIMEOUT
+        };
+
+        self.inner.wait_async_done(timeout)
+    }
+
+    /// Seeks to the frame on screen at `timestamp`, or the keyframe nearest to it with `SeekMode::Fast`, and maps it;
+    /// `None` if there is no frame there.
+    fn pull_frame_at_time(&mut self, timestamp: Duration) -> Result<Option<FrameView>> {
+        if timestamp >= self.duration {
+            return Ok(None);
+        }
+
+        self.seek_to_time_by_mode(timestamp, self.displayed_index(timestamp))?;
+
+        self.pull_current()
+    }
+
+    /// Grabs `n` consecutive frames starting at `start`, packed back-to-back into a single buffer.
+    ///
+    /// The returned shape is `(frames, height, width, channels)`,
+    /// where `frames` can be lower than `n` if the video runs out of frames before that.
+    pub fn get_frame_batch(&mut self, start: u64, n: u64) -> Result<(Vec<u8>, BatchShape)> {
+        let mut buf = Vec::new();
+        let mut layout: Option<FrameLayout> = None;
+        let mut count = 0;
+
+        let end = start.saturating_add(n).min(self.frame_count());
+
+        // only as many frames as the video has can end up in the batch, whatever `n` asks for
+        let frames = usize::try_from(end.saturating_sub(start))
+            .map_err(|_| VidSeqError::TooLarge { what: "batch" })?;
+
+        for index in start..end {
+            let frame = match self.pull_frame(index)? {
+                Some(frame) => frame,
+                None => break,
+            };
+
+            let layout = match layout {
+                Some(layout) if layout != frame.layout => {
+                    return Err(VidSeqError::ResolutionChanged {
+                        from: (layout.width as u32, layout.height as u32),
+                        to: (frame.layout.width as u32, frame.layout.height as u32),
+                    });
+                }
+                Some(layout) => layout,
+                None => {
+                    let size = frame
+                        .layout
+                        .frame_size()
+                        .checked_mul(frames)
+                        .ok_or(VidSeqError::TooLarge { what: "batch" })?;
+
+                    buf = vec![0u8; size];
+
+                    *layout.insert(frame.layout)
+                }
+            };
+
+            let offset = count * layout.frame_size();
+
+            layout.copy_packed(
+                frame.map.as_slice(),
+                &mut buf[offset..offset + layout.frame_size()],
+            )?;
+
+            count += 1;
+        }
+
+        let (height, width) = layout.map_or((0, 0), |l| (l.height, l.width));
+
+        let channels = layout.map_or(self.options.pixel_format.channels(), |l| l.channels());
+
+        buf.truncate(count * height * width * channels);
+
+        Ok((buf, (count, height, width, channels)))
+    }
+
+    /// Grabs the frame at `index` and splits it into a grid of `cols` by `rows` tiles, in row-major order.
+    ///
+    /// All tiles have the same size; if the frame dimensions are not divisible by the grid,
+    /// the remaining pixels on the right and bottom edges are cropped off.
+    pub fn get_frame_tiles(
+        &mut self,
+        index: u64,
+        cols: u32,
+        rows: u32,
+    ) -> Result<Option<Vec<RgbImage>>> {
+        if cols == 0 || rows == 0 {
+            return Err(VidSeqError::InvalidArgument(
+                "tile grid needs at least one column and row".to_owned(),
+            ));
+        }
+
+        let frame = match self.pull_frame(index)? {
+            Some(frame) => frame,
+            None => return Ok(None),
+        };
+
+        let tile_width = frame.layout.width / cols as usize;
+        let tile_height = frame.layout.height / rows as usize;
+
+        if tile_width == 0 || tile_height == 0 {
+            return Err(VidSeqError::InvalidArgument(
+                "tile grid is finer than the frame".to_owned(),
+            ));
+        }
+
+        let mut tiles = Vec::with_capacity((cols * rows) as usize);
+
+        for row in 0..rows as usize {
+            for col in 0..cols as usize {
+                let buf = frame.layout.copy_region(
+                    frame.map.as_slice(),
+                    col * tile_width,
+                    row * tile_height,
+                    tile_width,
+                    tile_height,
+                )?;
+
+                tiles.push(
+                    Frame::from_raw(
+                        frame.layout.format,
+                        tile_width as u32,
+                        tile_height as u32,
+                        buf,
+                    )?
+                    .into_rgb(),
+                );
+            }
+        }
+
+        Ok(Some(tiles))
+    }
+
+    /// Assumed amount of frames in this sequence, see struct documentation for caveats.
+    ///
+    /// This is exact when the container reports a frame count, see `frame_count_source`,
+    /// or with `LenPolicy::ExactLazy`, where the first call counts every frame (see `count_frames`).
+    pub fn len(&self) -> u64 {
+        if self.options.len_policy == LenPolicy::ExactLazy {
+            // without an exact count, the estimate is still better than nothing
+            return self.count_frames().unwrap_or(self.frames);
+        }
+
+        self.frame_count()
+    }
+
+    /// Decodes the whole video on a separate pipeline to count its frames exactly, which takes a while.
+    ///
+    /// The count is cached, later calls return it right away; from then on, it also bounds seeking instead of the estimate.
+    /// A failed count is remembered as well, later calls fail with `VidSeqError::CountFailed` instead of decoding again.
+    pub fn count_frames(&self) -> Result<u64> {
+        if let Some(frames) = self.exact_frames.get() {
+            return Ok(frames);
+        }
+
+        if self.count_failed.get() {
+            return Err(VidSeqError::CountFailed);
+        }
+
+        let frames = count_frames(&self.uri, DEFAULT_TIMEOUT)
+            .inspect_err(|_| self.count_failed.set(true))?;
+
+        self.exact_frames.set(Some(frames));
+
+        Ok(frames)
+    }
+
+    /// Best known amount of frames, without counting them.
+    fn frame_count(&self) -> u64 {
+        self.exact_frames.get().unwrap_or(self.frames)
+    }
+
+    /// Where `len` got its number from.
+    pub fn frame_count_source(&self) -> FrameCountSource {
+        self.frame_count_source
+    }
+
+    /// Whether the frame count bounding this sequence is exact, instead of estimated from the duration and frame rate.
+    ///
+    /// That is the case when the container reported it, or once `count_frames` has counted the frames.
+    pub fn frame_count_is_exact(&self) -> bool {
+        self.frame_count_source == FrameCountSource::Container || self.exact_frames.get().is_some()
+    }
+
+    /// What is known about the video from opening it, without decoding anything further.
+    pub fn info(&self) -> &VideoInfo {
+        &self.info
+    }
+
+    /// Index of the frame the video is currently positioned at.
+    pub fn current_index(&self) -> u64 {
+        self.current_index
+    }
+
+    /// Whether the video is positioned at its first frame.
+    pub fn at_start(&self) -> bool {
+        self.current_index == 0
+    }
+
+    /// Whether the video is positioned at its last frame, or ran past the end while stepping.
+    ///
+    /// This goes by `len` when the video has not ended yet, so it shares its caveats for estimated frame counts.
+    pub fn at_end(&self) -> bool {
+        self.inner.eos || self.current_index + 1 >= self.frame_count()
+    }
+
+    /// Size of the frames as `(width, height)`, which is the size of the last frame that was handed out.
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.dimensions
+    }
+
+    /// Name of the gstreamer element that decodes this video, e.g. `avdec_h264` or `vaapih264dec`.
+    pub fn decoder_name(&self) -> Option<&str> {
+        self.decoder.as_deref()
+    }
+
+    /// Frame rate of the video in frames per second, as read from its caps.
+    ///
+    /// Streams without a fixed frame rate report their maximum frame rate here if they declare one, and zero otherwise;
+    /// frames of the latter are spread evenly over the video if the container counts them, or treated as a single frame if not.
+    pub fn frame_rate(&self) -> f64 {
+        self.framerate.0 as f64 / self.framerate.1 as f64
+    }
+
+    /// Exact frame rate fraction of the video as `(numerator, denominator)`, e.g. `(30000, 1001)`.
+    pub fn frame_rate_fraction(&self) -> (i32, i32) {
+        self.framerate
+    }
+
+    /// Duration of the video as the container declares it, which is what frame counts are estimated from.
+    pub fn declared_duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Duration of the video up to the end of its last frame that actually decodes, `None` if no frame could be found near the end.
+    ///
+    /// This decodes the last second or so of the video, stepping until it ends, and adds a frame duration to the last timestamp;
+    /// a large difference from `declared_duration` means the container's metadata is off.
+    /// The sequence is left positioned at the last frame.
+    pub fn measured_duration(&mut self) -> Result<Option<Duration>> {
+        let start = self.duration.saturating_sub(Duration::from_secs(1));
+
+        self.seek_to_time(start)?;
+
+        self.current_index = self.displayed_index(start);
+
+        let mut last = None;
+
+        while let Some(pts) = self.preroll_pts()? {
+            self.current_index = self.displayed_index(pts);
+
+            last = Some(pts);
+
+            self.step_buffer(pts)?;
+        }
+
+        if let Some(pts) = last {
+            self.current_index = self.displayed_index(pts);
+        }
+
+        Ok(last.map(|pts| pts + self.per_frame))
+    }
+
+    /// The time segment that the video sink is currently operating in, as last announced by the pipeline.
+    ///
+    /// Useful to figure out why a seek landed where it did, especially on streams that do not start at zero.
+    pub fn segment(&self) -> Option<SegmentInfo> {
+        let segment = self.inner.segment.lock().unwrap();
+
+        let segment = segment.as_ref()?.downcast_ref::<gstreamer::ClockTime>()?;
+
+        Some(SegmentInfo {
+            rate: segment.rate(),
+            applied_rate: segment.applied_rate(),
+            start: segment.start().map(Into::into),
+            stop: segment.stop().map(Into::into),
+            time: segment.time().map(Into::into),
+            base: segment.base().map(Into::into),
+            position: segment.position().map(Into::into),
+        })
+    }
+
+    /// The clean aperture the stream signals within its coded frames, `None` if it signals none.
+    ///
+    /// Frames are cropped to it unless `VideoSequenceBuilder::crop_to_clean_aperture` was turned off.
+    pub fn clean_aperture(&self) -> Option<Rect> {
+        self.clean_aperture
+    }
+
+    /// How frames should be rotated and mirrored on display, `None` if the video does not say or they already are.
+    pub fn orientation(&self) -> Option<Orientation> {
+        self.orientation
+    }
+
+    /// The transformation from decoded pixel coordinates to display coordinates, as a row-major 3x3 matrix.
+    ///
+    /// A point `(x, y)` in the frame ends up at `M * (x, y, 1)` on display.
+    /// gstreamer only exposes this transform as the `image-orientation` tag, which covers the four rotations and their mirrored variants,
+    /// so this is `None` when the video carries no such tag, or when it was already applied (see `OrientationMode`).
+    pub fn display_matrix(&self) -> Option<[f64; 9]> {
+        let orientation = self.orientation?;
+
+        let (width, height) = self.dimensions;
+
+        Some(orientation.matrix(width as f64, height as f64))
+    }
+
+    /// Chapters as advertised by the container's table of contents, in order of appearance.
+    ///
+    /// Returns an empty list if the container did not provide a TOC.
+    pub fn chapters(&self) -> Vec<Chapter> {
+        let mut chapters = Vec::new();
+
+        if let Some(toc) = &self.inner.toc {
+            collect_chapters(&toc.entries(), &mut chapters);
+        }
+
+        chapters
+    }
+
+    /// Grabs a frame at the start of every chapter, see `chapters` and `get_frame`.
+    pub fn chapter_thumbnails(&mut self) -> Result<Vec<(Chapter, Option<RgbImage>)>> {
+        let mut thumbnails = Vec::new();
+
+        for chapter in self.chapters() {
+            let index = self.displayed_index(chapter.start);
+
+            let frame = self.get_frame(index)?;
+
+            thumbnails.push((chapter, frame));
+        }
+
+        Ok(thumbnails)
+    }
+
+    /// Index of the frame that is nearest to `timestamp`, clamped to the sequence; see `index_to_timestamp` for the other way around.
+    ///
+    /// Timestamps past the end map onto the last frame.
+    pub fn timestamp_to_index(&self, timestamp: Duration) -> u64 {
+        let (num, denom) = self.framerate;
+
+        let index = nearest_frame(timestamp, num, denom, self.per_frame) as u64;
+
+        index.min(self.frame_count().saturating_sub(1))
+    }
+
+    /// Index of the last frame that starts at or before `timestamp`, i.e. the one on screen then, clamped to the sequence.
+    fn displayed_index(&self, timestamp: Duration) -> u64 {
+        let (num, denom) = self.framerate;
+
+        let index = frames_in(timestamp, num, denom, self.per_frame) as u64;
+
+        index.min(self.frame_count().saturating_sub(1))
+    }
+
+    /// Where frame `index` starts, according to the frame rate.
+    ///
+    /// This is computed as an exact fraction, as repeatedly adding up a rounded frame duration drifts off
+    /// by whole frames at high indices for rates like 24000/1001.
+    pub fn index_to_timestamp(&self, index: u64) -> Duration {
+        let (num, denom) = self.framerate;
+
+        frame_start(index, num, denom, self.per_frame)
+    }
+}
+
+/// Where frame `index` starts at a frame rate of `num / denom`, or with `per_frame` each if there is no fixed rate.
+fn frame_start(index: u64, num: i32, denom: i32, per_frame: Duration) -> Duration {
+    let nanos = if num == 0 {
+        per_frame.as_nanos() * index as u128
+    } else {
+        let numer = index as u128 * denom as u128 * *gstreamer::ClockTime::SECOND as u128;
+
+        // round up, so that the timestamp never lies just before the start of the frame
+        numer.div_ceil(num as u128)
+    };
+
+    Duration::from_nanos(nanos.min(u64::MAX as u128) as u64)
+}
+
+/// How many whole frames fit in `duration` at a frame rate of `num / denom`, or with `per_frame` each if there is no fixed rate.
+fn frames_in(duration: Duration, num: i32, denom: i32, per_frame: Duration) -> u128 {
+    if num == 0 {
+        duration.as_nanos() / per_frame.as_nanos()
+    } else {
+        duration.as_nanos() * num as u128 / (denom as u128 * *gstreamer::ClockTime::SECOND as u128)
+    }
+}
+
+/// Index of the frame that starts nearest to `timestamp`, see `frames_in`.
+fn nearest_frame(timestamp: Duration, num: i32, denom: i32, per_frame: Duration) -> u128 {
+    frames_in(
+        timestamp.saturating_add(per_frame / 2),
+        num,
+        denom,
+        per_frame,
+    )
+}
+
+/// Shape of a frame batch, as `(frames, height, width, channels)`.
+pub type BatchShape = (usize, usize, usize, usize);
+
+/// Basic properties of an opened video, see `VideoSequence::info`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VideoInfo {
+    /// Size of the frames as they are handed out, which follows along with allowed resolution changes like `VideoSequence::dimensions`.
+    pub width: u32,
+    pub height: u32,
+    /// Frame rate as the fraction gstreamer reports, e.g. `(24000, 1001)`; for videos without a fixed rate,
+    /// their maximum frame rate if they declare one, `(0, 1)` otherwise. See `VideoSequence::frame_rate`.
+    pub framerate: (i32, i32),
+    pub duration: Duration,
+    /// Duration of a single frame, rounded down to whole nanoseconds.
+    pub per_frame: Duration,
+    /// Whether the decoder that got picked is hardware-accelerated, see `VideoSequence::decoder_name`.
+    pub hardware_decoder: bool,
+}
+
+/// Average cost of each seeking strategy, see `VideoSequence::measure_seek_costs`.
+#[derive(Debug, Clone, Copy)]
+pub struct SeekStats {
+    /// Time taken to step a single frame forward.
+    pub avg_step: Duration,
+    /// Time taken by a flushing, frame-accurate seek.
+    pub avg_flush_seek: Duration,
+}
+
+fn average(durations: &[Duration]) -> Duration {
+    if durations.is_empty() {
+        return Duration::ZERO;
+    }
+
+    durations.iter().sum::<Duration>() / durations.len() as u32
+}
+
+/// A snapshot of a time segment, see `VideoSequence::segment`.
+#[derive(Debug, Clone, Copy)]
+pub struct SegmentInfo {
+    pub rate: f64,
+    pub applied_rate: f64,
+    /// First timestamp that is played in this segment.
+    pub start: Option<Duration>,
+    /// Timestamp at which this segment stops playing.
+    pub stop: Option<Duration>,
+    /// Stream time that corresponds to `start`.
+    pub time: Option<Duration>,
+    /// Running time at which this segment started.
+    pub base: Option<Duration>,
+    pub position: Option<Duration>,
+}
+
+/// Basic facts about a video file, as gathered by `probe`.
+#[derive(Debug, Clone)]
+pub struct ProbeResult {
+    /// Codec description as reported by the demuxer, e.g. "H.264 / AVC".
+    pub codec: Option<String>,
+    /// Width and height of the decoded video.
+    pub dimensions: Option<(u32, u32)>,
+    pub duration: Option<Duration>,
+    pub seekable: bool,
+    pub has_video: bool,
+}
+
+/// Quickly inspects a video file without preparing it for frame extraction.
+///
+/// This only prerolls the pipeline long enough to read its caps, tags and queries, and then tears it down again,
+/// skipping the initial seek that `VideoSequence::open` does, which makes it the cheaper option to scan a library of files.
+pub fn probe<P: AsRef<Path>>(path: P) -> Result<ProbeResult> {
+    use gstreamer::prelude::PadExt;
+
+    let mut inner = VideoSequenceInner::new(
+        &path_to_uri(path.as_ref(), true)?,
+        &VideoSequenceBuilder::new(),
+    )?;
+
+    inner.set_state_with_timeout(gstreamer::State::Paused, DEFAULT_TIMEOUT)?;
+
+    let n_video: i32 = inner
+        .pipeline
+        .property("n-video")?
+        .get()
+        .map_err(|_| VidSeqError::Internal("n-video is not an integer".to_owned()))?;
+
+    let codec = inner.video_tags().and_then(|tags| {
+        tags.get::<gstreamer::tags::VideoCodec>()
+            .map(|codec| codec.get().to_owned())
+    });
+
+    let dimensions = inner
+        .appsink
+        .static_pad("sink")
+        .and_then(|pad| pad.current_caps())
+        .and_then(|caps| gstreamer_video::VideoInfo::from_caps(&caps).ok())
+        .map(|info| (info.width(), info.height()));
+
+    let duration = inner
+        .pipeline
+        .query_duration::<gstreamer::ClockTime>()
+        .map(Into::into);
+
+    let mut seeking = gstreamer::query::Seeking::new(gstreamer::Format::Time);
+
+    let seekable = inner.pipeline.query(&mut seeking) && seeking.result().0;
+
+    Ok(ProbeResult {
+        codec,
+        dimensions,
+        duration,
+        seekable,
+        has_video: n_video > 0,
+    })
+}
+
+/// Iterator over frames on a fixed time grid, see `VideoSequence::frames_on_grid`.
+pub struct GridFrames<'a> {
+    seq: &'a mut VideoSequence,
+    next: Option<Duration>,
+    interval: Duration,
+}
+
+impl Iterator for GridFrames<'_> {
+    type Item = Result<(Duration, RgbImage)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let timestamp = self.next?;
+
+        self.next = timestamp.checked_add(self.interval);
+
+        match self.seq.pull_frame_at_time(timestamp) {
+            Ok(Some(frame)) => Some(frame.to_image().map(|img| (timestamp, img))),
+            Ok(None) => {
+                self.next = None;
+
+                None
+            }
+            Err(e) => {
+                self.next = None;
+
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Iterator over consecutive frames, see `VideoSequence::frames`.
+pub struct FrameIter<'a> {
+    seq: &'a mut VideoSequence,
+    started: bool,
+    done: bool,
+}
+
+impl Iterator for FrameIter<'_> {
+    type Item = Result<RgbImage>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        if self.started {
+            if self.seq.current_index + 1 >= self.seq.frame_count() {
+                self.done = true;
+
+                return None;
+            }
+
+            if let Err(e) = self.seq.step(1) {
+                self.done = true;
+
+                return Some(Err(e));
+            }
+        }
+
+        self.started = true;
+
+        match self.seq.pull_current() {
+            Ok(Some(frame)) => Some(frame.to_image()),
+            Ok(None) => {
+                self.done = true;
+
+                None
+            }
+            Err(e) => {
+                self.done = true;
+
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// How a video wants its frames to be rotated and mirrored on display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Orientation {
+    /// Clockwise rotation in degrees, one of 0, 90, 180 or 270.
+    pub rotation: u32,
+    /// Whether to mirror horizontally before rotating.
+    pub flip: bool,
+}
+
+impl Orientation {
+    /// The EXIF `Orientation` value that describes the same transform, from 1 to 8.
+    pub fn exif(&self) -> u16 {
+        match (self.flip, self.rotation) {
+            (false, 90) => 6,
+            (false, 180) => 3,
+            (false, 270) => 8,
+            (false, _) => 1,
+            (true, 90) => 7,
+            (true, 180) => 4,
+            (true, 270) => 5,
+            (true, _) => 2,
+        }
+    }
+
+    /// Parses a gstreamer `image-orientation` tag value, e.g. `rotate-90` or `flip-rotate-180`.
+    fn from_tag(tag: &str) -> Option<Self> {
+        let (flip, rotation) = match tag.strip_prefix("flip-") {
+            Some(rest) => (true, rest),
+            None => (false, tag),
+        };
+
+        let rotation = match rotation {
+            "rotate-0" => 0,
+            "rotate-90" => 90,
+            "rotate-180" => 180,
+            "rotate-270" => 270,
+            _ => return None,
+        };
+
+        Some(Self { rotation, flip })
+    }
+
+    /// Turns a decoded frame upright.
+    pub fn apply(&self, img: &RgbImage) -> RgbImage {
+        use image::imageops;
+
+        let flipped;
+
+        let img = if self.flip {
+            flipped = imageops::flip_horizontal(img);
+            &flipped
+        } else {
+            img
+        };
+
+        match self.rotation {
+            90 => imageops::rotate90(img),
+            180 => imageops::rotate180(img),
+            270 => imageops::rotate270(img),
+            _ => img.clone(),
+        }
+    }
+
+    /// Row-major matrix mapping a `width` by `height` frame onto its display coordinates.
+    fn matrix(&self, width: f64, height: f64) -> [f64; 9] {
+        let flip = if self.flip {
+            [-1.0, 0.0, width, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0]
+        } else {
+            [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0]
+        };
+
+        let rotate = match self.rotation {
+            90 => [0.0, -1.0, height, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0],
+            180 => [-1.0, 0.0, width, 0.0, -1.0, height, 0.0, 0.0, 1.0],
+            270 => [0.0, 1.0, 0.0, -1.0, 0.0, width, 0.0, 0.0, 1.0],
+            _ => [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0],
+        };
+
+        let mut out = [0.0; 9];
+
+        for row in 0..3 {
+            for col in 0..3 {
+                out[row * 3 + col] = (0..3)
+                    .map(|k| rotate[row * 3 + k] * flip[k * 3 + col])
+                    .sum();
+            }
+        }
+
+        out
+    }
+}
+
+/// A single chapter entry from a video's table of contents.
+#[derive(Debug, Clone)]
+pub struct Chapter {
+    pub title: Option<String>,
+    pub start: Duration,
+    pub end: Option<Duration>,
+}
+
+fn collect_chapters(entries: &[gstreamer::TocEntry], chapters: &mut Vec<Chapter>) {
+    for entry in entries {
+        if entry.entry_type() == gstreamer::TocEntryType::Chapter {
+            if let Some((start, stop)) = entry.start_stop_times() {
+                let title = entry
+                    .tags()
+                    .and_then(|tags| tags.get::<gstreamer::tags::Title>())
+                    .map(|title| title.get().to_owned());
+
+                chapters.push(Chapter {
+                    title,
+                    start: Duration::from_nanos(start.max(0) as u64),
+                    end: (stop >= 0).then(|| Duration::from_nanos(stop as u64)),
+                });
+            }
+        }
+
+        collect_chapters(&entry.sub_entries(), chapters);
+    }
+}
+
+/// Converts a single RGB frame sample to an `image::RgbImage`, other supported pixel formats are converted to RGB.
+pub fn convert_sample_to_image(sample: gstreamer::Sample) -> Result<RgbImage> {
+    FrameView::from_sample(&sample, true, true)?.to_image()
+}
+
+/// Converts a single frame sample to a `Frame` of whichever pixel format it has.
+pub fn convert_sample_to_frame(sample: gstreamer::Sample) -> Result<Frame> {
+    FrameView::from_sample(&sample, true, true)?.to_frame()
+}
+
+/// A decoded frame in one of the supported pixel formats, see `VideoSequence::get_frame_dyn`.
+#[derive(Debug, Clone)]
+pub enum Frame {
+    Rgb(RgbImage),
+    Rgba(RgbaImage),
+    Gray(GrayImage),
+}
+
+impl Frame {
+    pub fn dimensions(&self) -> (u32, u32) {
+        match self {
+            Self::Rgb(img) => img.dimensions(),
+            Self::Rgba(img) => img.dimensions(),
+            Self::Gray(img) => img.dimensions(),
+        }
+    }
+
+    pub fn format(&self) -> PixelFormat {
+        match self {
+            Self::Rgb(_) => PixelFormat::Rgb,
+            Self::Rgba(_) => PixelFormat::Rgba,
+            Self::Gray(_) => PixelFormat::Gray8,
+        }
+    }
+
+    /// Converts the frame to RGB, dropping alpha.
+    pub fn into_rgb(self) -> RgbImage {
+        match self {
+            Self::Rgb(img) => img,
+            other => DynamicImage::from(other).into_rgb8(),
+        }
+    }
+
+    /// Wraps tightly packed pixel data of `format`.
+    fn from_raw(format: PixelFormat, width: u32, height: u32, buf: Vec<u8>) -> Result<Self> {
+        let frame = match format {
+            PixelFormat::Rgb => RgbImage::from_raw(width, height, buf).map(Self::Rgb),
+            PixelFormat::Rgba => RgbaImage::from_raw(width, height, buf).map(Self::Rgba),
+            PixelFormat::Gray8 => GrayImage::from_raw(width, height, buf).map(Self::Gray),
+        };
+
+        frame.ok_or_else(|| VidSeqError::InvalidFrame("image buffer was not sufficient".to_owned()))
+    }
+}
+
+impl From<Frame> for DynamicImage {
+    fn from(frame: Frame) -> Self {
+        match frame {
+            Frame::Rgb(img) => DynamicImage::ImageRgb8(img),
+            Frame::Rgba(img) => DynamicImage::ImageRgba8(img),
+            Frame::Gray(img) => DynamicImage::ImageLuma8(img),
+        }
+    }
+}
+
+/// Converts `duration` into a gstreamer clock time, which cannot hold the longest durations.
+fn clock_time(duration: Duration) -> Result<gstreamer::ClockTime> {
+    duration.try_into().map_err(|_| {
+        VidSeqError::InvalidArgument(format!("{:?} is too long for gstreamer", duration))
+    })
+}
+
+/// Position of the buffer of `sample` in the stream.
+fn stream_time(sample: &gstreamer::Sample) -> Option<Duration> {
+    let pts = sample.buffer()?.pts()?;
+
+    // timestamps are relative to the segment, which does not necessarily start at zero
+    let stream_time = sample
+        .segment()
+        .as_ref()
+        .and_then(|segment| segment.downcast_ref::<gstreamer::ClockTime>())
+        .and_then(|segment| segment.to_stream_time(pts))
+        .unwrap_or(pts);
+
+    Some(stream_time.into())
+}
+
+/// Reads the clean aperture from a buffer's crop meta, if the decoder attached one.
+fn clean_aperture(buffer: &gstreamer::BufferRef) -> Option<Rect> {
+    let (x, y, width, height) = buffer.meta::<gstreamer_video::VideoCropMeta>()?.rect();
+
+    Some(Rect {
+        x,
+        y,
+        width,
+        height,
+    })
+}
+
+/// A rectangle within a frame, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// A decoded frame, borrowed straight from gstreamer's buffer without copying it.
+///
+/// The underlying buffer is released once this is dropped, which returns it to the buffer pool when one is in use
+/// (see `VideoSequenceBuilder::buffer_pool`).
+pub struct FrameView {
+    layout: FrameLayout,
+    map: gstreamer::buffer::MappedBuffer<gstreamer::buffer::Readable>,
+}
+
+impl FrameView {
+    pub fn width(&self) -> u32 {
+        self.layout.width as u32
+    }
+
+    pub fn height(&self) -> u32 {
+        self.layout.height as u32
+    }
+
+    /// Pixel format of the frame, RGB unless `VideoSequenceBuilder::pixel_format` picked another one.
+    pub fn format(&self) -> PixelFormat {
+        self.layout.format
+    }
+
+    /// Distance in bytes between the start of two consecutive rows, which includes any padding.
+    pub fn stride(&self) -> usize {
+        self.layout.stride
+    }
+
+    /// The raw frame data starting at the first visible pixel, with every row padded to `stride` bytes.
+    pub fn as_slice(&self) -> &[u8] {
+        &self.map[self.layout.offset..]
+    }
+
+    /// The pixels of row `y`, without padding.
+    pub fn row(&self, y: u32) -> Option<&[u8]> {
+        let offset = self.layout.offset + y as usize * self.layout.stride;
+
+        if y as usize >= self.layout.height {
+            return None;
+        }
+
+        self.map.get(offset..offset + self.layout.row_size())
+    }
+
+    /// Copies the frame into an `image::RgbImage`, converting it to RGB if needed.
+    pub fn to_image(&self) -> Result<RgbImage> {
+        Ok(self.to_frame()?.into_rgb())
+    }
+
+    /// Copies the frame into a `Frame` of its own pixel format.
+    pub fn to_frame(&self) -> Result<Frame> {
+        let mut buf = vec![0u8; self.layout.frame_size()];
+
+        self.layout.copy_packed(self.map.as_slice(), &mut buf)?;
+
+        Frame::from_raw(
+            self.layout.format,
+            self.layout.width as u32,
+            self.layout.height as u32,
+            buf,
+        )
+    }
+
+    /// Copies the frame into an existing image of the same size, whatever container it uses; only works for RGB frames.
+    pub fn copy_into<C>(&self, buf: &mut ImageBuffer<Rgb<u8>, C>) -> Result<()>
+    where
+        C: Deref<Target = [u8]> + DerefMut,
+    {
+        if self.layout.format != PixelFormat::Rgb {
+            return Err(VidSeqError::InvalidArgument(format!(
+                "can only copy RGB frames into an RGB image, frame is {}",
+                self.layout.format.as_str()
+            )));
+        }
+
+        if buf.dimensions() != (self.width(), self.height()) {
+            return Err(VidSeqError::InvalidArgument(format!(
+                "image is {}x{}, while the frame is {}x{}",
+                buf.width(),
+                buf.height(),
+                self.width(),
+                self.height()
+            )));
+        }
+
+        self.layout.copy_packed(self.map.as_slice(), buf)
+    }
+
+    /// Copies the frame with every row padded to a multiple of `alignment` bytes, e.g. 16 or 64 for APIs that need aligned rows.
+    ///
+    /// Returns the data along with its stride, the padding bytes are zero. `alignment` has to be a power of two.
+    pub fn to_aligned(&self, alignment: usize) -> Result<(Vec<u8>, usize)> {
+        if !alignment.is_power_of_two() {
+            return Err(VidSeqError::InvalidArgument(
+                "alignment must be a power of two".to_owned(),
+            ));
+        }
+
+        let row_size = self.layout.row_size();
+
+        let stride = (row_size + alignment - 1) & !(alignment - 1);
+
+        let mut buf = vec![0u8; stride * self.layout.height];
+
+        for (y, row) in buf.chunks_exact_mut(stride).enumerate() {
+            let src = self.row(y as u32).ok_or_else(|| {
+                VidSeqError::InvalidFrame("could not copy full image buffer".to_owned())
+            })?;
+
+            row[..row_size].copy_from_slice(src);
+        }
+
+        Ok((buf, stride))
+    }
+
+    /// Maps the buffer of `sample`, cropped to its clean aperture if `crop` is set and one is attached.
+    ///
+    /// With `download` set, a DMABuf is copied into system memory first, instead of being mapped directly.
+    fn from_sample(sample: &gstreamer::Sample, crop: bool, download: bool) -> Result<Self> {
+        let caps = sample.caps().ok_or(VidSeqError::NoCaps)?;
+        let buffer = sample.buffer_owned().ok_or(VidSeqError::NoBuffer)?;
+
+        let mut layout = FrameLayout::from_caps(caps)?;
+
+        if let Some(rect) = clean_aperture(&buffer).filter(|_| crop) {
+            layout = layout.cropped(rect)?;
+        }
+
+        let dmabuf = is_dmabuf(&buffer);
+
+        let buffer = if dmabuf && download {
+            buffer.copy_deep().map_err(|_| {
+                VidSeqError::InvalidFrame(
+                    "could not copy DMABuf frame into system memory".to_owned(),
+                )
+            })?
+        } else {
+            buffer
+        };
+
+        let map = buffer.into_mapped_buffer_readable().map_err(|_| {
+            if dmabuf && !download {
+                VidSeqError::InvalidFrame(
+                    "could not map DMABuf frame, try VideoSequenceBuilder::system_memory"
+                        .to_owned(),
+                )
+            } else {
+                VidSeqError::InvalidFrame("could not map image buffer".to_owned())
+            }
+        })?;
+
+        Ok(Self { layout, map })
+    }
+}
+
+/// Memory layout of a decoded frame inside a gstreamer buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FrameLayout {
+    format: PixelFormat,
+    width: usize,
+    height: usize,
+    stride: usize,
+    /// Where the first visible pixel starts, non-zero when cropped.
+    offset: usize,
+}
+
+impl FrameLayout {
+    fn from_caps(caps: &gstreamer::CapsRef) -> Result<Self> {
+        let info = gstreamer_video::VideoInfo::from_caps(caps)?;
+
+        let format = PixelFormat::from_video_format(info.format()).ok_or_else(|| {
+            VidSeqError::UnsupportedFormat {
+                format: info.format().to_str().to_owned(),
+            }
+        })?;
+
+        if info.width() == 0 || info.height() == 0 {
+            return Err(VidSeqError::InvalidFrame("frame has no pixels".to_owned()));
+        }
+
+        let stride = info.stride()[0];
+
+        if stride <= 0 {
+            return Err(VidSeqError::InvalidFrame(
+                "frame has an unsupported stride".to_owned(),
+            ));
+        }
+
+        Ok(Self {
+            format,
+            width: info.width() as usize,
+            height: info.height() as usize,
+            stride: stride as usize,
+            offset: 0,
+        })
+    }
+
+    /// Narrows this layout down to `rect`.
+    fn cropped(self, rect: Rect) -> Result<Self> {
+        let (x, y) = (rect.x as usize, rect.y as usize);
+        let (width, height) = (rect.width as usize, rect.height as usize);
+
+        if width == 0 || height == 0 || x + width > self.width || y + height > self.height {
+            return Err(VidSeqError::InvalidFrame(
+                "clean aperture exceeds frame bounds".to_owned(),
+            ));
+        }
+
+        Ok(Self {
+            format: self.format,
+            width,
+            height,
+            stride: self.stride,
+            offset: self.offset + y * self.stride + x * self.channels(),
+        })
+    }
+
+    fn channels(&self) -> usize {
+        self.format.channels()
+    }
+
+    /// Size of a single row without any padding.
+    fn row_size(&self) -> usize {
+        self.width * self.channels()
+    }
+
+    /// Size of the whole frame without any padding.
+    fn frame_size(&self) -> usize {
+        self.row_size() * self.height
+    }
+
+    /// Copies the (possibly padded) rows in the buffer `data` into the tightly packed `out`.
+    fn copy_packed(&self, data: &[u8], out: &mut [u8]) -> Result<()> {
+        let row_size = self.row_size();
+
+        if self.height > 0 && data.len() < self.offset + self.stride * (self.height - 1) + row_size
+        {
+            return Err(VidSeqError::InvalidFrame(
+                "could not copy full image buffer".to_owned(),
+            ));
+        }
+
+        for (y, row) in out.chunks_exact_mut(row_size).take(self.height).enumerate() {
+            let offset = self.offset + y * self.stride;
+
+            row.copy_from_slice(&data[offset..offset + row_size]);
+        }
+
+        Ok(())
+    }
+
+    /// Copies a rectangular region out of the (possibly padded) rows in `data`, tightly packed.
+    fn copy_region(
+        &self,
+        data: &[u8],
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> Result<Vec<u8>> {
+        if x + width > self.width || y + height > self.height {
+            return Err(VidSeqError::InvalidArgument(
+                "region exceeds frame bounds".to_owned(),
+            ));
+        }
+
+        let row_size = width * self.channels();
+
+        let mut out = vec![0u8; row_size * height];
+
+        for (row_y, row) in out.chunks_exact_mut(row_size).enumerate() {
+            let offset = self.offset + (y + row_y) * self.stride + x * self.channels();
+
+            row.copy_from_slice(data.get(offset..offset + row_size).ok_or_else(|| {
+                VidSeqError::InvalidFrame("could not copy full image buffer".to_owned())
+            })?);
+        }
+
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, time::Duration};
+
+    use gstreamer::{
+        prelude::{Cast, ObjectExt},
+        traits::ElementExt,
+        MessageView,
+    };
+
+    use super::{
+        check_or_init_gst, clock_time, frame_start, frames_in, nearest_frame, SeekMode,
+        VideoSequence,
+    };
+
+    const PER_FRAME_25: Duration = Duration::from_millis(40);
+
+    /// 1001/24000 of a second, rounded down like `VideoSequence` does.
+    const PER_FRAME_NTSC_FILM: Duration = Duration::from_nanos(41_708_333);
+
+    /// Encodes a small gray frame at each of `pts` into a Matroska file in the temp directory, named after `name`.
+    ///
+    /// Every frame lasts until the next one starts, the last one for `last`; `framerate` goes into the caps as is.
+    fn write_video(name: &str, pts: &[Duration], last: Duration, framerate: (i32, i32)) -> PathBuf {
+        use gstreamer::prelude::GstBinExt;
+
+        check_or_init_gst().unwrap();
+
+        let path = std::env::temp_dir().join(format!("vidseq-{}-{}.mkv", name, std::process::id()));
+
+        let pipeline = gstreamer::parse_launch(
+            "appsrc name=src format=time ! jpegenc ! matroskamux ! filesink name=sink",
+        )
+        .unwrap()
+        .downcast::<gstreamer::Pipeline>()
+        .unwrap();
+
+        pipeline
+            .by_name("sink")
+            .unwrap()
+            .set_property("location", path.to_str().unwrap())
+            .unwrap();
+
+        let src = pipeline
+            .by_name("src")
+            .unwrap()
+            .downcast::<gstreamer_app::AppSrc>()
+            .unwrap();
+
+        src.set_caps(Some(
+            &gstreamer::Caps::builder("video/x-raw")
+                .field("format", "GRAY8")
+                .field("width", 64i32)
+                .field("height", 48i32)
+                .field(
+                    "framerate",
+                    gstreamer::Fraction::new(framerate.0, framerate.1),
+                )
+                .build(),
+        ));
+
+        pipeline.set_state(gstreamer::State::Playing).unwrap();
+
+        for (i, &start) in pts.iter().enumerate() {
+            let end = pts.get(i + 1).copied().unwrap_or(start + last);
+
+            let mut buffer = gstreamer::Buffer::from_mut_slice(vec![(i * 16 % 256) as u8; 64 * 48]);
+
+            let buffer_mut = buffer.get_mut().unwrap();
+
+            buffer_mut.set_pts(clock_time(start).unwrap());
+            buffer_mut.set_duration(clock_time(end - start).unwrap());
+
+            src.push_buffer(buffer).unwrap();
+        }
+
+        src.end_of_stream().unwrap();
+
+        let msg = pipeline.bus().unwrap().timed_pop_filtered(
+            Some(gstreamer::ClockTime::from_seconds(10)),
+            &[gstreamer::MessageType::Eos, gstreamer::MessageType::Error],
+        );
+
+        pipeline.set_state(gstreamer::State::Null).unwrap();
+
+        assert!(
+            matches!(
+                msg.as_ref().map(|msg| msg.view()),
+                Some(MessageView::Eos(_))
+            ),
+            "writing {} failed: {:?}",
+            path.display(),
+            msg
+        );
+
+        path
+    }
+
+    #[test]
+    fn single_frame_video_has_one_frame() {
+        // a single frame at 25 fps lasts exactly one frame duration
+        assert_eq!(frames_in(PER_FRAME_25, 25, 1, PER_FRAME_25), 1);
+        assert_eq!(frame_start(0, 25, 1, PER_FRAME_25), Duration::ZERO);
+    }
+
+    #[test]
+    fn video_shorter_than_a_frame_has_no_whole_frames() {
+        // callers round this up to the one frame that is still there
+        assert_eq!(frames_in(Duration::from_millis(10), 25, 1, PER_FRAME_25), 0);
+    }
+
+    #[test]
+    fn frames_in_without_fixed_rate_uses_frame_duration() {
+        let per_frame = Duration::from_millis(100);
+
+        assert_eq!(frames_in(Duration::from_secs(1), 0, 1, per_frame), 10);
+        assert_eq!(frame_start(3, 0, 1, per_frame), Duration::from_millis(300));
+    }
+
+    #[test]
+    fn frame_start_is_exact_for_integer_rates() {
+        assert_eq!(frame_start(25, 25, 1, PER_FRAME_25), Duration::from_secs(1));
+        assert_eq!(frames_in(Duration::from_secs(1), 25, 1, PER_FRAME_25), 25);
+    }
+
+    #[test]
+    fn timestamps_round_trip_at_high_indices_for_fractional_rates() {
+        for index in (0..100)
+            .chain(99_990..100_010)
+            .chain([1_000_000, 10_000_000])
+        {
+            let timestamp = frame_start(index, 24000, 1001, PER_FRAME_NTSC_FILM);
+
+            assert_eq!(
+                nearest_frame(timestamp, 24000, 1001, PER_FRAME_NTSC_FILM),
+                index as u128,
+                "nearest frame to the start of frame {}",
+                index
+            );
+            assert_eq!(
+                frames_in(timestamp, 24000, 1001, PER_FRAME_NTSC_FILM),
+                index as u128,
+                "frame on screen at the start of frame {}",
+                index
+            );
+        }
+    }
+
+    #[test]
+    fn frame_start_does_not_drift_for_fractional_rates() {
+        // 100000 frames at 24000/1001 take exactly 4170.8333... seconds
+        assert_eq!(
+            frame_start(100_000, 24000, 1001, PER_FRAME_NTSC_FILM),
+            Duration::from_nanos(4_170_833_333_334)
+        );
+    }
+
+    #[test]
+    fn single_frame_file_has_one_frame() {
+        let path = write_video("single-frame", &[Duration::ZERO], PER_FRAME_25, (25, 1));
+
+        let mut seq = VideoSequence::open(&path).unwrap();
+
+        assert_eq!(seq.len(), 1);
+        assert!(seq.get_frame(0).unwrap().is_some());
+        assert_eq!(seq.frames().count(), 1);
+        assert!(seq.get_frame(1).unwrap().is_none());
+        assert_eq!(seq.len(), 1);
+    }
+
+    #[test]
+    fn still_without_frame_rate_is_a_single_frame() {
+        let path = write_video("still", &[Duration::ZERO], Duration::from_secs(1), (0, 1));
+
+        let mut seq = VideoSequence::open(&path).unwrap();
+
+        assert_eq!(seq.frame_rate_fraction(), (0, 1));
+        assert_eq!(seq.len(), 1);
+        assert!(seq.get_frame(0).unwrap().is_some());
+    }
+
+    #[test]
+    fn variable_frame_rate_frames_follow_buffer_timestamps() {
+        let ms = Duration::from_millis;
+
+        // frames on a 25 fps grid, some of which are held for two slots
+        let pts = [0, 40, 80, 160, 200, 280, 320].map(ms);
+
+        let path = write_video("vfr", &pts, PER_FRAME_25, (25, 1));
+
+        let mut seq = VideoSequence::open(&path).unwrap();
+
+        assert_eq!(seq.len(), 9);
+
+        for index in 0..seq.len() {
+            assert!(seq.get_frame(index).unwrap().is_some());
+
+            let pts = seq.current_pts().unwrap();
+
+            assert!(
+                pts.abs_diff(seq.index_to_timestamp(index)) <= PER_FRAME_25,
+                "frame {} stepped to {:?}",
+                index,
+                pts
+            );
+        }
+
+        // seeking backwards always lands on the frame that is on screen at that time
+        for index in (0..seq.len()).rev() {
+            let timestamp = seq.index_to_timestamp(index);
+
+            let displayed = pts.iter().copied().filter(|&pts| pts <= timestamp).max();
+
+            assert!(seq.get_frame(index).unwrap().is_some());
+            assert_eq!(seq.current_pts(), displayed, "frame {}", index);
+        }
+    }
+
+    #[test]
+    fn fast_seek_takes_index_from_buffer_timestamp() {
+        let ms = Duration::from_millis;
+
+        let pts = [0, 40, 80, 240, 280].map(ms);
+
+        let path = write_video("vfr-fast", &pts, PER_FRAME_25, (25, 1));
+
+        let mut seq = VideoSequence::builder()
+            .seek_mode(SeekMode::Fast)
+            .open(&path)
+            .unwrap();
+
+        // 200ms is closest to the frame at 240ms, which sits where frame 6 would be
+        assert!(seq.get_frame(5).unwrap().is_some());
+        assert_eq!(seq.current_pts(), Some(ms(240)));
+        assert_eq!(seq.current_index(), 6);
+    }
+
+    #[test]
+    fn malformed_files_fail_to_open() {
+        let path = write_video(
+            "truncated",
+            &[Duration::ZERO, PER_FRAME_25],
+            PER_FRAME_25,
+            (25, 1),
+        );
+
+        let data = std::fs::read(&path).unwrap();
+
+        // cut off within the header, before any frame
+        std::fs::write(&path, &data[..100.min(data.len() / 2)]).unwrap();
+
+        assert!(VideoSequence::open(&path).is_err());
+
+        let junk = std::env::temp_dir().join(format!("vidseq-junk-{}.mkv", std::process::id()));
+
+        std::fs::write(
+            &junk,
+            (0..4096u32)
+                .map(|i| (i * 7919 % 251) as u8)
+                .collect::<Vec<_>>(),
+        )
+        .unwrap();
+
+        assert!(VideoSequence::open(&junk).is_err());
+
+        std::fs::write(&junk, b"").unwrap();
+
+        assert!(VideoSequence::open(&junk).is_err());
+    }
 }
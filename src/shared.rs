@@ -0,0 +1,97 @@
+use std::{
+    path::Path,
+    sync::{Condvar, Mutex},
+};
+
+use image::RgbImage;
+
+use crate::{Result, VidSeqError, VideoSequence, VideoSequenceBuilder};
+
+/// A pool of independently opened decoders for the same video, to serve frames to several threads at once.
+///
+/// Every request takes whichever decoder is idle, and waits for one to free up if they are all busy.
+pub struct SharedVideoSequence {
+    idle: Mutex<Vec<VideoSequence>>,
+    returned: Condvar,
+    frames: u64,
+}
+
+impl SharedVideoSequence {
+    /// Opens `path` with `options` once for every one of `decoders`, see `VideoSequenceBuilder::open`.
+    pub fn open<P: AsRef<Path>>(
+        path: P,
+        options: &VideoSequenceBuilder,
+        decoders: usize,
+    ) -> Result<Self> {
+        if decoders == 0 {
+            return Err(VidSeqError::InvalidArgument(
+                "pool needs at least one decoder".to_owned(),
+            ));
+        }
+
+        let first = options.open(path)?;
+
+        let frames = first.len();
+
+        let mut idle = Vec::with_capacity(decoders);
+
+        for _ in 1..decoders {
+            idle.push(first.try_clone()?);
+        }
+
+        idle.push(first);
+
+        Ok(Self {
+            idle: Mutex::new(idle),
+            returned: Condvar::new(),
+            frames,
+        })
+    }
+
+    /// See `VideoSequence::get_frame`, this blocks while every decoder is busy.
+    pub fn get_frame(&self, index: u64) -> Result<Option<RgbImage>> {
+        self.with_decoder(|seq| seq.get_frame(index))
+    }
+
+    /// Runs `f` on an idle decoder, waiting for one to free up if needed.
+    pub fn with_decoder<T>(&self, f: impl FnOnce(&mut VideoSequence) -> T) -> T {
+        let mut seq = {
+            let mut idle = self
+                .returned
+                .wait_while(self.idle.lock().unwrap(), |idle| idle.is_empty())
+                .unwrap();
+
+            Lease {
+                pool: self,
+                seq: idle.pop(),
+            }
+        };
+
+        f(seq.seq.as_mut().unwrap())
+    }
+
+    /// See `VideoSequence::len`.
+    pub fn len(&self) -> u64 {
+        self.frames
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames == 0
+    }
+}
+
+/// A decoder taken out of the pool, which goes back in once this is dropped, even when unwinding.
+struct Lease<'a> {
+    pool: &'a SharedVideoSequence,
+    seq: Option<VideoSequence>,
+}
+
+impl Drop for Lease<'_> {
+    fn drop(&mut self) {
+        if let Some(seq) = self.seq.take() {
+            self.pool.idle.lock().unwrap().push(seq);
+
+            self.pool.returned.notify_one();
+        }
+    }
+}